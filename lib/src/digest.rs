@@ -0,0 +1,139 @@
+use alloc::format;
+use alloc::string::{String, ToString};
+use sha3::{Digest, Keccak256};
+
+use crate::encoding::{hex_to_address, hex_to_bytes32};
+use crate::types::{Eip712Domain, OwnershipClaim, TypedData};
+
+// Keccak256 over the concatenation of the given byte strings
+pub fn keccak_concat(parts: &[&[u8]]) -> [u8; 32] {
+    let mut hasher = Keccak256::new();
+    for part in parts {
+        hasher.update(part);
+    }
+    hasher.finalize().into()
+}
+
+// Hash a plaintext message the way `personal_sign` does (EIP-191 version 0x45)
+pub fn eip191_digest(message: &str) -> [u8; 32] {
+    let mut hasher = Keccak256::new();
+    hasher.update(b"\x19Ethereum Signed Message:\n");
+    hasher.update(message.len().to_string().as_bytes());
+    hasher.update(message.as_bytes());
+    hasher.finalize().into()
+}
+
+// Compute the EIP-712 domain separator
+pub fn eip712_domain_separator(domain: &Eip712Domain) -> [u8; 32] {
+    let type_hash = keccak_concat(&[b"EIP712Domain(string name,string version,uint256 chainId,address verifyingContract)"]);
+    
+    let mut chain_id = [0u8; 32];
+    chain_id[24..].copy_from_slice(&domain.chain_id.to_be_bytes());
+    let mut verifying_contract = [0u8; 32];
+    verifying_contract[12..].copy_from_slice(&hex_to_address(&domain.verifying_contract));
+    
+    keccak_concat(&[
+        &type_hash,
+        &keccak_concat(&[domain.name.as_bytes()]),
+        &keccak_concat(&[domain.version.as_bytes()]),
+        &chain_id,
+        &verifying_contract,
+    ])
+}
+
+// Compute the EIP-712 struct hash of an ownership claim
+pub fn ownership_claim_hash(claim: &OwnershipClaim) -> [u8; 32] {
+    let type_hash = keccak_concat(&[b"OwnershipClaim(string statement,bytes32 merkleRoot,bytes32 nonce,uint256 expiresAt)"]);
+    
+    let mut expires_at = [0u8; 32];
+    expires_at[24..].copy_from_slice(&claim.expires_at.to_be_bytes());
+    
+    keccak_concat(&[
+        &type_hash,
+        &keccak_concat(&[claim.statement.as_bytes()]),
+        &hex_to_bytes32(&claim.merkle_root),
+        &hex_to_bytes32(&claim.nonce),
+        &expires_at,
+    ])
+}
+
+// Hash typed data the way `eth_signTypedData_v4` does
+pub fn eip712_digest(typed_data: &TypedData) -> [u8; 32] {
+    keccak_concat(&[
+        b"\x19\x01",
+        &eip712_domain_separator(&typed_data.domain),
+        &ownership_claim_hash(&typed_data.claim),
+    ])
+}
+
+// Hash a digest into the `SafeMessage` a Safe's owners sign for it, as the Safe's
+// `getMessageHashForSafe` does for ERC-1271 `isValidSignature(bytes32, bytes)`: EIP-712 over
+// the domain of the Safe's address and chain (Safe 1.3.0 and later) and the digest as message
+pub fn safe_message_digest(safe: &[u8; 20], chain_id: u64, message_digest: &[u8; 32]) -> [u8; 32] {
+    let domain_type_hash = keccak_concat(&[b"EIP712Domain(uint256 chainId,address verifyingContract)"]);
+    let message_type_hash = keccak_concat(&[b"SafeMessage(bytes message)"]);
+    
+    let mut chain_id_word = [0u8; 32];
+    chain_id_word[24..].copy_from_slice(&chain_id.to_be_bytes());
+    let mut safe_word = [0u8; 32];
+    safe_word[12..].copy_from_slice(safe);
+    
+    keccak_concat(&[
+        b"\x19\x01",
+        &keccak_concat(&[&domain_type_hash, &chain_id_word, &safe_word]),
+        &keccak_concat(&[&message_type_hash, &keccak_concat(&[message_digest])]),
+    ])
+}
+
+// Message a key's nullifier for a scope is computed over
+pub fn nullifier_message(scope: &str) -> String {
+    format!("Token ownership nullifier for scope: {}", scope)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn eip712_digest_matches_reference_encoder() {
+        // The same domain and claim declared with alloy's `sol!` and `eip712_domain!` and hashed
+        // with its `separator`, `eip712_hash_struct` and `eip712_signing_hash`
+        let typed_data = TypedData {
+            domain: Eip712Domain {
+                name: "Token Ownership Proof".to_string(),
+                version: "1".to_string(),
+                chain_id: 1,
+                verifying_contract: "0xcccccccccccccccccccccccccccccccccccccccc".to_string(),
+            },
+            claim: OwnershipClaim {
+                statement: "I own at least 1000 tokens of this snapshot".to_string(),
+                merkle_root: "0x1111111111111111111111111111111111111111111111111111111111111111".to_string(),
+                nonce: "0x2222222222222222222222222222222222222222222222222222222222222222".to_string(),
+                expires_at: 1_700_000_000,
+            },
+        };
+        assert_eq!(
+            hex::encode(eip712_domain_separator(&typed_data.domain)),
+            "6c45934890cc5f44d353afe6cd0f7433a323eba5ca0ad2234a51386ce787ac7f"
+        );
+        assert_eq!(
+            hex::encode(ownership_claim_hash(&typed_data.claim)),
+            "83cda720664a542f4ae6fbcfa0919205f0c4aa8ac448c7cfe69e4004011fdce8"
+        );
+        assert_eq!(
+            hex::encode(eip712_digest(&typed_data)),
+            "a14da12647bb4acfedd61ab36f3c120e523b95f41819e467be4f858dbe1c1436"
+        );
+    }
+
+    #[test]
+    fn safe_message_digest_matches_reference_encoder() {
+        // `SafeMessage { message: 0x4242...42 }` in the domain of Safe 0x5afe...5afe on mainnet,
+        // hashed with alloy's EIP-712 `eip712_signing_hash`
+        let safe = hex_to_address("0x5afe5afe5afe5afe5afe5afe5afe5afe5afe5afe");
+        assert_eq!(
+            hex::encode(safe_message_digest(&safe, 1, &[0x42; 32])),
+            "222dd1f8e5ff9852e2d5c67274c6bd7ffeac94e9d252d79de7f35612f83fbbc6"
+        );
+    }
+}
@@ -0,0 +1,32 @@
+use alloc::format;
+use alloc::string::String;
+
+use crate::error::ClaimError;
+
+// Parse a hex string, with or without 0x prefix, into a 32-byte array
+pub fn parse_bytes32(hex: &str) -> Option<[u8; 32]> {
+    let hex_str = hex.strip_prefix("0x").unwrap_or(hex);
+    hex::decode(hex_str).ok()?.try_into().ok()
+}
+
+// Convert a hex string from the public inputs to a 32-byte array
+pub fn hex_to_bytes32(hex: &str) -> [u8; 32] {
+    parse_bytes32(hex).unwrap_or_else(|| panic!("Expected a 32-byte hex value, got {:?}", hex))
+}
+
+// Parse a hex string, with or without 0x prefix, into a 20-byte Ethereum address
+pub fn parse_address(hex: &str) -> Option<[u8; 20]> {
+    let hex_str = hex.strip_prefix("0x").unwrap_or(hex);
+    hex::decode(hex_str).ok()?.try_into().ok()
+}
+
+// Convert a hex string to a 20-byte Ethereum address
+pub fn hex_to_address(hex: &str) -> [u8; 20] {
+    parse_address(hex).unwrap_or_else(|| panic!("Expected a 20-byte hex address, got {:?}", hex))
+}
+
+// Canonical form of an address as recovered from a signature: 0x-prefixed lowercase hex
+pub fn canonical_address(hex: &str) -> Result<String, ClaimError> {
+    let address = parse_address(hex).ok_or(ClaimError::InvalidAddress)?;
+    Ok(format!("0x{}", hex::encode(address)))
+}
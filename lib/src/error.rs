@@ -0,0 +1,110 @@
+use core::fmt;
+
+// Reasons a claim can fail verification. The program aborts with these codes and the
+// script's pre-validation reports them, so both describe a problem the same way.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ClaimError {
+    MissingHexPrefix,
+    InvalidHex,
+    InvalidSignatureLength,
+    InvalidRecoveryId,
+    InvalidSignature,
+    RecoveryFailed,
+    InvalidPublicKey,
+    InvalidProofNode,
+    DuplicateAddress,
+    RootMismatch,
+    MissingNullifierProof,
+    InvalidNullifierProof,
+    BalanceOverflow,
+    WrongProofDepth,
+    ProofIndexOutOfRange,
+    MissingInclusionProof,
+    MalformedMultiproof,
+    UnknownRoot,
+    InvalidRlp,
+    InvalidStorageProof,
+    StorageBalanceMismatch,
+    AccountBalanceMismatch,
+    SafeNotDeployed,
+    NotSafeOwner,
+    SafeThresholdNotMet,
+    HighS,
+    InvalidAddress,
+    UnsortedSafeOwners,
+}
+
+impl ClaimError {
+    // Stable numeric code of the error
+    pub fn code(&self) -> u32 {
+        match self {
+            ClaimError::MissingHexPrefix => 1,
+            ClaimError::InvalidHex => 2,
+            ClaimError::InvalidSignatureLength => 3,
+            ClaimError::InvalidRecoveryId => 4,
+            ClaimError::InvalidSignature => 5,
+            ClaimError::RecoveryFailed => 6,
+            ClaimError::InvalidPublicKey => 7,
+            ClaimError::InvalidProofNode => 8,
+            ClaimError::DuplicateAddress => 9,
+            ClaimError::RootMismatch => 10,
+            ClaimError::MissingNullifierProof => 11,
+            ClaimError::InvalidNullifierProof => 12,
+            ClaimError::BalanceOverflow => 13,
+            ClaimError::WrongProofDepth => 14,
+            ClaimError::ProofIndexOutOfRange => 15,
+            ClaimError::MissingInclusionProof => 16,
+            ClaimError::MalformedMultiproof => 17,
+            ClaimError::UnknownRoot => 18,
+            ClaimError::InvalidRlp => 19,
+            ClaimError::InvalidStorageProof => 20,
+            ClaimError::StorageBalanceMismatch => 21,
+            ClaimError::AccountBalanceMismatch => 22,
+            ClaimError::SafeNotDeployed => 23,
+            ClaimError::NotSafeOwner => 24,
+            ClaimError::SafeThresholdNotMet => 25,
+            ClaimError::HighS => 26,
+            ClaimError::InvalidAddress => 27,
+            ClaimError::UnsortedSafeOwners => 28,
+        }
+    }
+    
+    pub fn description(&self) -> &'static str {
+        match self {
+            ClaimError::MissingHexPrefix => "signature is missing the 0x prefix",
+            ClaimError::InvalidHex => "signature is not valid hex",
+            ClaimError::InvalidSignatureLength => "signature is neither 64 bytes (EIP-2098) nor 65 to 72 bytes (r, s and v)",
+            ClaimError::InvalidRecoveryId => "signature recovery value v is not 0, 1, 27, 28 or an EIP-155 value",
+            ClaimError::InvalidSignature => "signature r or s value is invalid",
+            ClaimError::RecoveryFailed => "no public key can be recovered from the signature",
+            ClaimError::InvalidPublicKey => "recovered public key is malformed",
+            ClaimError::InvalidProofNode => "Merkle proof node is not a 32-byte hex value",
+            ClaimError::DuplicateAddress => "address was already claimed by an earlier claim",
+            ClaimError::RootMismatch => "Merkle proof does not lead to the expected root",
+            ClaimError::MissingNullifierProof => "nullifier proof is required when a nullifier scope is set",
+            ClaimError::InvalidNullifierProof => "nullifier proof does not verify for the claimed address's key",
+            ClaimError::BalanceOverflow => "adding the balance overflows the 256-bit total",
+            ClaimError::WrongProofDepth => "Merkle proof length does not match the public tree depth",
+            ClaimError::ProofIndexOutOfRange => "Merkle proof index does not fit in a tree of the public depth",
+            ClaimError::MissingInclusionProof => "claim has no Merkle, storage or account proof and no multiproof is given",
+            ClaimError::MalformedMultiproof => "multiproof repeats a leaf index or has too few or too many sibling nodes",
+            ClaimError::UnknownRoot => "claim names a root that is not in the public inputs",
+            ClaimError::InvalidRlp => "trie node or account is not canonical RLP",
+            ClaimError::InvalidStorageProof => "account or storage proof does not lead to the expected root",
+            ClaimError::StorageBalanceMismatch => "claimed balance does not match the balance in the token's storage",
+            ClaimError::AccountBalanceMismatch => "claimed balance does not match the account's ETH balance",
+            ClaimError::SafeNotDeployed => "Safe account does not exist at the state root",
+            ClaimError::NotSafeOwner => "Safe claim is signed by an address that is not an owner of the Safe",
+            ClaimError::SafeThresholdNotMet => "fewer distinct owners signed the Safe claim than its threshold",
+            ClaimError::HighS => "signature s is in the upper half of the curve order (not low-s, EIP-2)",
+            ClaimError::InvalidAddress => "Safe address is not a 20-byte hex value",
+            ClaimError::UnsortedSafeOwners => "Safe owner signatures are not sorted by ascending owner address",
+        }
+    }
+}
+
+impl fmt::Display for ClaimError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "E{:02}: {}", self.code(), self.description())
+    }
+}
@@ -0,0 +1,32 @@
+//! Types and hashing shared by the token ownership program and its script
+//!
+//! Everything the program computes over its inputs lives here, so host-side tooling can
+//! reproduce exactly what the program checks. The crate is `no_std` with `alloc` when the
+//! default `std` feature is disabled.
+
+#![cfg_attr(not(feature = "std"), no_std)]
+
+extern crate alloc;
+
+mod digest;
+mod encoding;
+mod error;
+mod merkle;
+mod mpt;
+mod nullifier;
+mod rlp;
+mod signature;
+mod storage;
+mod types;
+
+pub use digest::*;
+pub use encoding::*;
+pub use error::ClaimError;
+pub use merkle::*;
+pub use mpt::*;
+pub use nullifier::*;
+pub use rlp::*;
+pub use ruint::aliases::U256;
+pub use signature::*;
+pub use storage::*;
+pub use types::*;
@@ -0,0 +1,356 @@
+use alloc::string::{String, ToString};
+use alloc::vec;
+use alloc::vec::Vec;
+use ruint::aliases::U256;
+use sha3::{Digest, Keccak256};
+
+use crate::digest::keccak_concat;
+use crate::encoding::{hex_to_address, parse_bytes32};
+use crate::error::ClaimError;
+use crate::types::{InclusionBranches, MultiProof, TreeScheme};
+
+// Hash a leaf (address, balance) pair using keccak256
+pub fn hash_leaf(address: &str, balance: U256) -> [u8; 32] {
+    let address = address.to_lowercase();
+    let balance = balance.to_string();
+    let leaf_str = address + ":" + &balance;
+    
+    let mut hasher = Keccak256::new();
+    hasher.update(leaf_str.as_bytes());
+    let result = hasher.finalize();
+    
+    let mut hash = [0u8; 32];
+    hash.copy_from_slice(&result);
+    hash
+}
+
+// Hash a leaf (address, balance) pair with the RFC 6962 leaf prefix:
+// keccak256(0x00 || "<lowercase address>:<decimal balance>")
+pub fn hash_leaf_rfc6962(address: &str, balance: U256) -> [u8; 32] {
+    let leaf_str = address.to_lowercase() + ":" + &balance.to_string();
+    keccak_concat(&[&[RFC6962_LEAF_PREFIX], leaf_str.as_bytes()])
+}
+
+// Prefixes that keep leaf and inner node preimages apart in the `rfc6962` scheme
+pub const RFC6962_LEAF_PREFIX: u8 = 0x00;
+pub const RFC6962_NODE_PREFIX: u8 = 0x01;
+
+// Deepest tree whose leaf indices fit in the `u32` proof index
+pub const MAX_TREE_DEPTH: u32 = 32;
+
+// Leaf hash used to fill a snapshot up to the next power of two. No (address, balance)
+// pair hashes to it, so padding leaves can never be claimed.
+pub const PADDING_LEAF: [u8; 32] = [0u8; 32];
+
+// Compute the Merkle root from a leaf hash and inclusion proof, rejecting proofs that
+// do not have exactly `depth` nodes or whose index lies outside a tree of that depth
+pub fn compute_inclusion_root(commitment: [u8; 32], proof: &InclusionBranches, depth: u32) -> Result<[u8; 32], ClaimError> {
+    compute_ordered_inclusion_root(commitment, proof, depth, TreeScheme::Legacy)
+}
+
+// Same as `compute_inclusion_root`, with inner nodes hashed as keccak256(0x01 || left || right)
+pub fn compute_rfc6962_inclusion_root(commitment: [u8; 32], proof: &InclusionBranches, depth: u32) -> Result<[u8; 32], ClaimError> {
+    compute_ordered_inclusion_root(commitment, proof, depth, TreeScheme::Rfc6962)
+}
+
+// Walk a proof in which bit `i` of the index says whether the node at level `i` is a
+// right child, hashing each pair of children with `scheme`
+fn compute_ordered_inclusion_root(
+    commitment: [u8; 32],
+    proof: &InclusionBranches,
+    depth: u32,
+    scheme: TreeScheme,
+) -> Result<[u8; 32], ClaimError> {
+    if proof.proof.len() != depth as usize {
+        return Err(ClaimError::WrongProofDepth);
+    }
+    if u64::from(proof.index) >> depth != 0 {
+        return Err(ClaimError::ProofIndexOutOfRange);
+    }
+    
+    let bits = proof.index;
+    let mut root = commitment;
+    
+    for (i, hash_hex) in proof.proof.iter().enumerate() {
+        let hash = parse_bytes32(hash_hex).ok_or(ClaimError::InvalidProofNode)?;
+        
+        root = if bits & (1 << i) == 0 {
+            scheme.hash_node(&root, &hash)
+        } else {
+            scheme.hash_node(&hash, &root)
+        };
+    }
+    
+    Ok(root)
+}
+
+// Compute the Merkle root from the (index, leaf hash) pairs of several leaves and the
+// sibling nodes of a multiproof, in one pass from the leaves up. Only the index-ordered
+// `legacy` and `rfc6962` schemes are supported.
+pub fn compute_multiproof_root(
+    leaves: &[(u32, [u8; 32])],
+    proof: &[String],
+    depth: u32,
+    scheme: TreeScheme,
+) -> Result<[u8; 32], ClaimError> {
+    assert!(scheme != TreeScheme::OpenZeppelin, "Multiproofs are not supported for OpenZeppelin trees");
+    
+    let mut nodes = Vec::with_capacity(leaves.len());
+    for &(index, leaf) in leaves {
+        if u64::from(index) >> depth != 0 {
+            return Err(ClaimError::ProofIndexOutOfRange);
+        }
+        nodes.push((index, leaf));
+    }
+    nodes.sort_unstable_by_key(|&(index, _)| index);
+    if nodes.is_empty() || nodes.windows(2).any(|pair| pair[0].0 == pair[1].0) {
+        return Err(ClaimError::MalformedMultiproof);
+    }
+    
+    let mut siblings = proof.iter();
+    for _ in 0..depth {
+        let mut parents = Vec::with_capacity(nodes.len());
+        let mut i = 0;
+        while i < nodes.len() {
+            let (position, node) = nodes[i];
+            // Both children are known: hash them together without a proof node
+            if position % 2 == 0 && nodes.get(i + 1).is_some_and(|&(next, _)| next == position + 1) {
+                parents.push((position / 2, scheme.hash_node(&node, &nodes[i + 1].1)));
+                i += 2;
+                continue;
+            }
+            let sibling = siblings.next().ok_or(ClaimError::MalformedMultiproof)?;
+            let sibling = parse_bytes32(sibling).ok_or(ClaimError::InvalidProofNode)?;
+            let parent = if position % 2 == 0 {
+                scheme.hash_node(&node, &sibling)
+            } else {
+                scheme.hash_node(&sibling, &node)
+            };
+            parents.push((position / 2, parent));
+            i += 1;
+        }
+        nodes = parents;
+    }
+    
+    if siblings.next().is_some() {
+        return Err(ClaimError::MalformedMultiproof);
+    }
+    Ok(nodes[0].1)
+}
+
+impl MultiProof {
+    // Merge individual inclusion proofs of distinct leaves in one index-ordered tree into
+    // a multiproof, keeping only the sibling nodes `compute_multiproof_root` cannot derive
+    pub fn from_proofs(proofs: &[InclusionBranches]) -> Self {
+        // Each node remembers one leaf below it, whose proof holds that node's siblings
+        let mut nodes: Vec<(u32, usize)> = proofs.iter().enumerate().map(|(leaf, proof)| (proof.index, leaf)).collect();
+        nodes.sort_unstable();
+        nodes.dedup_by_key(|&mut (position, _)| position);
+        
+        let depth = proofs.first().map_or(0, |proof| proof.proof.len());
+        let mut proof = Vec::new();
+        for level in 0..depth {
+            let mut parents = Vec::with_capacity(nodes.len());
+            let mut i = 0;
+            while i < nodes.len() {
+                let (position, leaf) = nodes[i];
+                if position % 2 == 0 && nodes.get(i + 1).is_some_and(|&(next, _)| next == position + 1) {
+                    i += 2;
+                } else {
+                    proof.push(proofs[leaf].proof[level].clone());
+                    i += 1;
+                }
+                parents.push((position / 2, leaf));
+            }
+            nodes = parents;
+        }
+        
+        MultiProof { indices: proofs.iter().map(|proof| proof.index).collect(), proof }
+    }
+}
+
+// Hash a leaf the way OpenZeppelin's `StandardMerkleTree` does for `["address", "uint256"]`
+// values: keccak256(keccak256(abi.encode(address, balance)))
+pub fn hash_leaf_openzeppelin(address: &str, balance: U256) -> [u8; 32] {
+    let mut encoded = [0u8; 64];
+    encoded[12..32].copy_from_slice(&hex_to_address(address));
+    encoded[32..].copy_from_slice(&balance.to_be_bytes::<32>());
+    keccak_concat(&[&keccak_concat(&[&encoded])])
+}
+
+// Hash two nodes in ascending byte order, as OpenZeppelin's `MerkleProof` does
+fn hash_sorted_pair(a: &[u8; 32], b: &[u8; 32]) -> [u8; 32] {
+    if a <= b {
+        keccak_concat(&[a, b])
+    } else {
+        keccak_concat(&[b, a])
+    }
+}
+
+// Compute the root of an OpenZeppelin `StandardMerkleTree` from a leaf hash and proof.
+// That tree is complete but not perfect, so a proof has `depth` or `depth - 1` nodes; the
+// index is not needed to order the pairs and only has to fit in the tree.
+pub fn compute_sorted_inclusion_root(commitment: [u8; 32], proof: &InclusionBranches, depth: u32) -> Result<[u8; 32], ClaimError> {
+    if proof.proof.len() as u64 + 1 < u64::from(depth) || proof.proof.len() as u64 > u64::from(depth) {
+        return Err(ClaimError::WrongProofDepth);
+    }
+    if u64::from(proof.index) >> depth != 0 {
+        return Err(ClaimError::ProofIndexOutOfRange);
+    }
+    
+    proof.proof.iter().try_fold(commitment, |node, hash_hex| {
+        let hash = parse_bytes32(hash_hex).ok_or(ClaimError::InvalidProofNode)?;
+        Ok(hash_sorted_pair(&node, &hash))
+    })
+}
+
+impl TreeScheme {
+    // Hash an (address, balance) leaf under this scheme
+    pub fn hash_leaf(&self, address: &str, balance: U256) -> [u8; 32] {
+        match self {
+            TreeScheme::Legacy => hash_leaf(address, balance),
+            TreeScheme::OpenZeppelin => hash_leaf_openzeppelin(address, balance),
+            TreeScheme::Rfc6962 => hash_leaf_rfc6962(address, balance),
+        }
+    }
+    
+    // Hash a left and right child into their parent under this scheme
+    pub fn hash_node(&self, left: &[u8; 32], right: &[u8; 32]) -> [u8; 32] {
+        match self {
+            TreeScheme::Legacy => keccak_concat(&[left, right]),
+            TreeScheme::OpenZeppelin => hash_sorted_pair(left, right),
+            TreeScheme::Rfc6962 => keccak_concat(&[&[RFC6962_NODE_PREFIX], left, right]),
+        }
+    }
+    
+    // Compute the Merkle root from a leaf hash and inclusion proof under this scheme
+    pub fn inclusion_root(&self, commitment: [u8; 32], proof: &InclusionBranches, depth: u32) -> Result<[u8; 32], ClaimError> {
+        match self {
+            TreeScheme::Legacy => compute_inclusion_root(commitment, proof, depth),
+            TreeScheme::OpenZeppelin => compute_sorted_inclusion_root(commitment, proof, depth),
+            TreeScheme::Rfc6962 => compute_rfc6962_inclusion_root(commitment, proof, depth),
+        }
+    }
+}
+
+// Merkle tree over leaf hashes for the index-ordered `legacy` and `rfc6962` schemes, built the
+// way `compute_inclusion_root` verifies: the leaf index bits, lowest first, say whether each
+// node is a left or right child
+#[derive(Debug, Clone)]
+pub struct MerkleTree {
+    // levels[0] holds the leaves and the last level holds only the root
+    levels: Vec<Vec<[u8; 32]>>,
+}
+
+impl MerkleTree {
+    // Build a tree from a non-empty list of leaf hashes, filling it up to the next power
+    // of two with `PADDING_LEAF` and hashing inner nodes with `scheme`
+    pub fn new(mut leaves: Vec<[u8; 32]>, scheme: TreeScheme) -> Self {
+        assert!(scheme != TreeScheme::OpenZeppelin, "OpenZeppelin trees are built with `StandardMerkleTree`");
+        assert!(!leaves.is_empty(), "A Merkle tree needs at least one leaf");
+        let width = leaves.len().next_power_of_two();
+        assert!(width.trailing_zeros() <= MAX_TREE_DEPTH, "Too many leaves for a tree of depth {}", MAX_TREE_DEPTH);
+        leaves.resize(width, PADDING_LEAF);
+        
+        let mut levels = vec![leaves];
+        while let Some(nodes) = levels.last().filter(|nodes| nodes.len() > 1) {
+            let parents = nodes.chunks(2).map(|pair| scheme.hash_node(&pair[0], &pair[1])).collect();
+            levels.push(parents);
+        }
+        
+        MerkleTree { levels }
+    }
+    
+    pub fn root(&self) -> [u8; 32] {
+        self.levels[self.levels.len() - 1][0]
+    }
+    
+    // Number of sibling hashes in every inclusion proof
+    pub fn depth(&self) -> u32 {
+        (self.levels.len() - 1) as u32
+    }
+    
+    // Inclusion proof of the leaf at `index`, in the format `compute_inclusion_root` and
+    // `compute_rfc6962_inclusion_root` expect
+    pub fn proof(&self, index: usize) -> InclusionBranches {
+        let proof = self.levels[..self.depth() as usize].iter().enumerate()
+            .map(|(level, nodes)| hex::encode(nodes[(index >> level) ^ 1]))
+            .collect();
+        InclusionBranches { index: index as u32, proof }
+    }
+}
+
+// OpenZeppelin `StandardMerkleTree`: leaves sorted by hash and stored in reverse at the end
+// of a flat array in which node `i` has children `2i + 1` and `2i + 2`
+#[derive(Debug, Clone)]
+pub struct StandardMerkleTree {
+    nodes: Vec<[u8; 32]>,
+    // Position in `nodes` of each leaf, in the order the leaves were given
+    positions: Vec<usize>,
+}
+
+impl StandardMerkleTree {
+    // Build a tree from a non-empty list of leaf hashes, giving the same root as
+    // `StandardMerkleTree.of` for the same values
+    pub fn new(leaves: Vec<[u8; 32]>) -> Self {
+        assert!(!leaves.is_empty(), "A Merkle tree needs at least one leaf");
+        assert!(leaves.len() <= 1 << MAX_TREE_DEPTH, "Too many leaves for a tree of depth {}", MAX_TREE_DEPTH);
+        
+        let mut order: Vec<usize> = (0..leaves.len()).collect();
+        order.sort_by_key(|&index| leaves[index]);
+        
+        let size = 2 * leaves.len() - 1;
+        let mut nodes = vec![[0u8; 32]; size];
+        let mut positions = vec![0; leaves.len()];
+        for (rank, &index) in order.iter().enumerate() {
+            positions[index] = size - 1 - rank;
+            nodes[size - 1 - rank] = leaves[index];
+        }
+        for i in (0..size - leaves.len()).rev() {
+            nodes[i] = hash_sorted_pair(&nodes[2 * i + 1], &nodes[2 * i + 2]);
+        }
+        
+        StandardMerkleTree { nodes, positions }
+    }
+    
+    pub fn root(&self) -> [u8; 32] {
+        self.nodes[0]
+    }
+    
+    // Number of sibling hashes in the longest inclusion proof
+    pub fn depth(&self) -> u32 {
+        self.nodes.len().ilog2()
+    }
+    
+    // Inclusion proof of the leaf given at `index`, in the format `compute_sorted_inclusion_root` expects
+    pub fn proof(&self, index: usize) -> InclusionBranches {
+        let mut position = self.positions[index];
+        let mut proof = Vec::new();
+        while position > 0 {
+            let sibling = if position % 2 == 1 { position + 1 } else { position - 1 };
+            proof.push(hex::encode(self.nodes[sibling]));
+            position = (position - 1) / 2;
+        }
+        InclusionBranches { index: index as u32, proof }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn openzeppelin_tree_matches_reference_root() {
+        // Values and root from the OpenZeppelin merkle-tree README example
+        let leaves = vec![
+            hash_leaf_openzeppelin("0x1111111111111111111111111111111111111111", U256::from(5_000_000_000_000_000_000u64)),
+            hash_leaf_openzeppelin("0x2222222222222222222222222222222222222222", U256::from(2_500_000_000_000_000_000u64)),
+        ];
+        let tree = StandardMerkleTree::new(leaves.clone());
+        assert_eq!(hex::encode(tree.root()), "d4dee0beab2d53f2cc83e567171bd2820e49898130a22622b10ead383e90bd77");
+
+        for (index, leaf) in leaves.into_iter().enumerate() {
+            assert_eq!(TreeScheme::OpenZeppelin.inclusion_root(leaf, &tree.proof(index), tree.depth()), Ok(tree.root()));
+        }
+    }
+}
@@ -0,0 +1,182 @@
+use alloc::vec::Vec;
+
+use crate::digest::keccak_concat;
+use crate::error::ClaimError;
+use crate::rlp::Rlp;
+
+// Verify a Merkle Patricia trie proof for `key`, as returned by `eth_getProof`, against the
+// trie root. Returns the value stored at the key, or `None` if the proof shows the key is
+// absent. State and storage tries are "secure" tries, so callers pass keccak256 of the
+// account address or storage slot as the key.
+pub fn verify_mpt_proof(root: &[u8; 32], key: &[u8], proof: &[Vec<u8>]) -> Result<Option<Vec<u8>>, ClaimError> {
+    let path: Vec<u8> = key.iter().flat_map(|byte| [byte >> 4, byte & 0x0f]).collect();
+    let mut remaining = &path[..];
+    let mut nodes = proof.iter();
+
+    // The node to check next: the first proof node must hash to the root, later ones to the
+    // reference in their parent. Nodes shorter than 32 bytes are embedded in their parent.
+    let mut expected_hash = Some(*root);
+    let mut embedded: Option<&[u8]> = None;
+
+    loop {
+        let node = match (embedded.take(), expected_hash.take()) {
+            (Some(node), _) => node,
+            (None, Some(hash)) => {
+                let node = nodes.next().ok_or(ClaimError::InvalidStorageProof)?;
+                if keccak_concat(&[node]) != hash {
+                    return Err(ClaimError::InvalidStorageProof);
+                }
+                &node[..]
+            }
+            (None, None) => return Err(ClaimError::InvalidStorageProof),
+        };
+        let items = Rlp::decode(node)?.as_list()?;
+
+        let next = match items.len() {
+            // Branch node: one child per nibble and a value for keys ending here
+            17 => match remaining.split_first() {
+                None => return finish(items[16].as_bytes()?, nodes.next()),
+                Some((&nibble, rest)) => {
+                    remaining = rest;
+                    items[usize::from(nibble)]
+                }
+            },
+            // Leaf or extension node with a hex-prefix encoded partial path
+            2 => {
+                let (partial, is_leaf) = decode_hex_prefix(items[0].as_bytes()?)?;
+                if is_leaf {
+                    let value = if remaining == &partial[..] { items[1].as_bytes()? } else { &[] };
+                    return finish(value, nodes.next());
+                }
+                if !remaining.starts_with(&partial) {
+                    return finish(&[], nodes.next());
+                }
+                remaining = &remaining[partial.len()..];
+                items[1]
+            }
+            _ => return Err(ClaimError::InvalidStorageProof),
+        };
+
+        match next {
+            Rlp::Bytes([]) => return finish(&[], nodes.next()),
+            Rlp::Bytes(hash) => expected_hash = Some(hash.try_into().map_err(|_| ClaimError::InvalidStorageProof)?),
+            Rlp::List { encoded, .. } => embedded = Some(encoded),
+        }
+    }
+}
+
+// End a proof walk, which must have used every proof node
+fn finish(value: &[u8], unused_node: Option<&Vec<u8>>) -> Result<Option<Vec<u8>>, ClaimError> {
+    if unused_node.is_some() {
+        return Err(ClaimError::InvalidStorageProof);
+    }
+    Ok((!value.is_empty()).then(|| value.to_vec()))
+}
+
+// Decode a hex-prefix encoded path into nibbles and whether it belongs to a leaf
+fn decode_hex_prefix(encoded: &[u8]) -> Result<(Vec<u8>, bool), ClaimError> {
+    let (&first, rest) = encoded.split_first().ok_or(ClaimError::InvalidStorageProof)?;
+    let flag = first >> 4;
+    if flag > 3 || (flag & 1 == 0 && first & 0x0f != 0) {
+        return Err(ClaimError::InvalidStorageProof);
+    }
+    let mut nibbles = Vec::with_capacity(rest.len() * 2 + 1);
+    if flag & 1 == 1 {
+        nibbles.push(first & 0x0f);
+    }
+    nibbles.extend(rest.iter().flat_map(|byte| [byte >> 4, byte & 0x0f]));
+    Ok((nibbles, flag & 2 == 2))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::vec;
+
+    // Nodes of a trie built with alloy-trie's `HashBuilder` from the keys 0x123456 = 0x01,
+    // 0x123457 = 0x02, 0x1234a0 = 40 x 0xab, 0x12f000 = 0x03 and 0x9abcde = 40 x 0xcd:
+    //   root branch: 1 -> extension "2" -> branch: 3 -> extension "4" -> branch: 5 -> [branch]
+    //                                                                           a -> leaf
+    //                                          f -> [leaf]
+    //                9 -> leaf
+    // Nodes in brackets are shorter than 32 bytes, so they are embedded in their parent
+    // instead of being proof nodes.
+    const ROOT: &str = "066f19aedae60a7206288595d74bd43834762f74aed4847f13d16c5ad1fac0bd";
+    const ROOT_BRANCH: &str = "f85180a00d8b9672f025624552876d56319255cea0d87fa694d619babfdef406e620983880808080808080a03d269f76d3c56bee350ac7a828b8b2a6b7743a2124108ce715304a3b552b0b0280808080808080";
+    const EXTENSION_12: &str = "e212a0b3cf25965b5054d9a49f37430378db90c5ad716a01a559ed465ebd425007e6f8";
+    const BRANCH_12: &str = "f5808080a0cad34f8523fb5ead23a922cc0e38c7520bcadb12581c63c76b4f6f6cc7c38f548080808080808080808080c48230000380";
+    const EXTENSION_1234: &str = "e214a0b56739be9f636544c22f199a0e8acf55fa0cebdcdaf5d0640b07bc6eebbfa6b8";
+    const BRANCH_1234: &str = "f8468080808080d5808080808080c22001c2200280808080808080808080808080a01f25dc8fc00ba66d965fbf398daef57370a8f8962b675e02d3ba469ed144e9d3808080808080";
+    const LEAF_1234A0: &str = "ea30a8abababababababababababababababababababababababababababababababababababababababab";
+    const LEAF_9ABCDE: &str = "ed833abcdea8cdcdcdcdcdcdcdcdcdcdcdcdcdcdcdcdcdcdcdcdcdcdcdcdcdcdcdcdcdcdcdcdcdcdcdcdcdcdcdcd";
+
+    fn root() -> [u8; 32] {
+        hex::decode(ROOT).unwrap().try_into().unwrap()
+    }
+
+    fn proof(nodes: &[&str]) -> Vec<Vec<u8>> {
+        nodes.iter().map(|node| hex::decode(node).unwrap()).collect()
+    }
+
+    #[test]
+    fn inclusion_proofs_through_extension_and_embedded_nodes() {
+        let path_to_1234 = [ROOT_BRANCH, EXTENSION_12, BRANCH_12, EXTENSION_1234, BRANCH_1234];
+        let cases = [
+            // Leaf inside an embedded branch inside a hashed branch
+            ([0x12, 0x34, 0x56], proof(&path_to_1234), vec![0x01]),
+            ([0x12, 0x34, 0x57], proof(&path_to_1234), vec![0x02]),
+            // Hashed leaf below two extension nodes
+            ([0x12, 0x34, 0xa0], proof(&[&path_to_1234[..], &[LEAF_1234A0]].concat()), vec![0xab; 40]),
+            // Leaf embedded directly in a hashed branch
+            ([0x12, 0xf0, 0x00], proof(&path_to_1234[..3]), vec![0x03]),
+            // Hashed leaf right below the root
+            ([0x9a, 0xbc, 0xde], proof(&[ROOT_BRANCH, LEAF_9ABCDE]), vec![0xcd; 40]),
+        ];
+        for (key, nodes, value) in cases {
+            assert_eq!(verify_mpt_proof(&root(), &key, &nodes), Ok(Some(value)), "key {}", hex::encode(key));
+        }
+    }
+
+    #[test]
+    fn exclusion_proofs() {
+        let path_to_1234 = [ROOT_BRANCH, EXTENSION_12, BRANCH_12, EXTENSION_1234, BRANCH_1234];
+        let cases = [
+            // Empty slot in the root branch
+            ([0x55, 0x55, 0x55], proof(&[ROOT_BRANCH])),
+            // Diverges from an embedded leaf
+            ([0x12, 0xff, 0x00], proof(&path_to_1234[..3])),
+            // Diverges inside an extension node
+            ([0x12, 0x35, 0x00], proof(&path_to_1234[..4])),
+            // Empty slot in an embedded branch
+            ([0x12, 0x34, 0x58], proof(&path_to_1234)),
+            // Diverges from a hashed leaf
+            ([0x9a, 0xbc, 0xdf], proof(&[ROOT_BRANCH, LEAF_9ABCDE])),
+        ];
+        for (key, nodes) in cases {
+            assert_eq!(verify_mpt_proof(&root(), &key, &nodes), Ok(None), "key {}", hex::encode(key));
+        }
+    }
+
+    #[test]
+    fn invalid_proofs_are_rejected() {
+        let key = [0x12, 0x34, 0xa0];
+        let nodes = proof(&[ROOT_BRANCH, EXTENSION_12, BRANCH_12, EXTENSION_1234, BRANCH_1234, LEAF_1234A0]);
+        assert!(verify_mpt_proof(&root(), &key, &nodes).is_ok());
+
+        let mut tampered = nodes.clone();
+        *tampered[5].last_mut().unwrap() ^= 1;
+        assert_eq!(verify_mpt_proof(&root(), &key, &tampered), Err(ClaimError::InvalidStorageProof));
+
+        let mut wrong_root = root();
+        wrong_root[0] ^= 1;
+        assert_eq!(verify_mpt_proof(&wrong_root, &key, &nodes), Err(ClaimError::InvalidStorageProof));
+
+        assert_eq!(verify_mpt_proof(&root(), &key, &nodes[..5]), Err(ClaimError::InvalidStorageProof));
+        assert_eq!(verify_mpt_proof(&root(), &key, &[]), Err(ClaimError::InvalidStorageProof));
+
+        // Nodes past the end of the walk are not allowed either
+        let mut extended = nodes.clone();
+        extended.push(hex::decode(LEAF_9ABCDE).unwrap());
+        assert_eq!(verify_mpt_proof(&root(), &key, &extended), Err(ClaimError::InvalidStorageProof));
+    }
+}
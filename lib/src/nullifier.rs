@@ -0,0 +1,137 @@
+use alloc::format;
+use k256::ecdsa::SigningKey;
+use k256::elliptic_curve::hash2curve::{ExpandMsgXmd, GroupDigest};
+use k256::elliptic_curve::ops::Reduce;
+use k256::elliptic_curve::sec1::{FromEncodedPoint, ToEncodedPoint};
+use k256::elliptic_curve::PrimeField;
+use k256::sha2::{Digest, Sha256};
+use k256::{AffinePoint, EncodedPoint, ProjectivePoint, Scalar, Secp256k1};
+
+use crate::digest::{keccak_concat, nullifier_message};
+use crate::encoding::parse_bytes32;
+use crate::error::ClaimError;
+use crate::types::NullifierProof;
+
+// Domain separation tag of the secp256k1 SSWU hash-to-curve suite of RFC 9380 PLUME uses
+const HASH_TO_CURVE_DST: &[u8] = b"QUUX-V01-CS02-with-secp256k1_XMD:SHA-256_SSWU_RO_";
+
+// Compressed SEC1 encoding of a point
+fn compress(point: &ProjectivePoint) -> EncodedPoint {
+    point.to_affine().to_encoded_point(true)
+}
+
+// Point a key's nullifier for a scope is the multiple of: H(message || compressed key)
+fn scope_point(scope: &str, pubkey: &ProjectivePoint) -> Result<ProjectivePoint, ClaimError> {
+    let message = nullifier_message(scope);
+    Secp256k1::hash_from_bytes::<ExpandMsgXmd<Sha256>>(&[message.as_bytes(), compress(pubkey).as_bytes()], &[HASH_TO_CURVE_DST])
+        .map_err(|_| ClaimError::InvalidNullifierProof)
+}
+
+// Fiat-Shamir challenge over the generator, key, scope point, nullifier and both commitments
+fn challenge(points: [&ProjectivePoint; 6]) -> Scalar {
+    let mut hasher = Sha256::new();
+    for point in points {
+        hasher.update(compress(point).as_bytes());
+    }
+    <Scalar as Reduce<k256::U256>>::reduce_bytes(&hasher.finalize())
+}
+
+// Parse a compressed point, rejecting the identity
+fn parse_point(hex_str: &str) -> Option<ProjectivePoint> {
+    let bytes = hex::decode(hex_str.strip_prefix("0x").unwrap_or(hex_str)).ok()?;
+    if bytes.len() != 33 {
+        return None;
+    }
+    let point = Option::<AffinePoint>::from(AffinePoint::from_encoded_point(&EncodedPoint::from_bytes(bytes).ok()?))?;
+    Some(point.into())
+}
+
+// Parse a scalar below the curve order
+fn parse_scalar(hex_str: &str) -> Option<Scalar> {
+    Option::from(Scalar::from_repr(parse_bytes32(hex_str)?.into()))
+}
+
+// Compute the PLUME nullifier of a key for a scope (ERC-7524, version 1) with its proof. The
+// nullifier sk * H(message || pk) is the same every time the key computes it, unlike an
+// ECDSA signature, whose nonce the signer is free to choose. The proof's own nonce is
+// derived from the key and the scope point.
+pub fn create_nullifier_proof(signing_key: &SigningKey, scope: &str) -> Result<NullifierProof, ClaimError> {
+    let secret: Scalar = *signing_key.as_nonzero_scalar().as_ref();
+    let pubkey = ProjectivePoint::GENERATOR * secret;
+    let scope_point = scope_point(scope, &pubkey)?;
+    let nullifier = scope_point * secret;
+    
+    let nonce_hash = Sha256::new()
+        .chain_update(secret.to_bytes())
+        .chain_update(compress(&scope_point).as_bytes())
+        .finalize();
+    let nonce = <Scalar as Reduce<k256::U256>>::reduce_bytes(&nonce_hash);
+    let c = challenge([
+        &ProjectivePoint::GENERATOR, &pubkey, &scope_point, &nullifier,
+        &(ProjectivePoint::GENERATOR * nonce), &(scope_point * nonce),
+    ]);
+    let s = nonce + secret * c;
+    Ok(NullifierProof {
+        nullifier: format!("0x{}", hex::encode(compress(&nullifier).as_bytes())),
+        c: format!("0x{}", hex::encode(c.to_bytes())),
+        s: format!("0x{}", hex::encode(s.to_bytes())),
+    })
+}
+
+// Verify a PLUME nullifier proof for the uncompressed hex public key recovered from a claim
+// and return the value committed for it, keccak256 of the compressed nullifier point. The
+// proof shows the nullifier and the key share one secret key without revealing it.
+pub fn verify_nullifier_proof(pubkey_hex: &str, scope: &str, proof: &NullifierProof) -> Result<[u8; 32], ClaimError> {
+    let pubkey_bytes = hex::decode(pubkey_hex).map_err(|_| ClaimError::InvalidPublicKey)?;
+    let pubkey = EncodedPoint::from_bytes(pubkey_bytes).ok()
+        .and_then(|encoded| Option::<AffinePoint>::from(AffinePoint::from_encoded_point(&encoded)))
+        .map(ProjectivePoint::from)
+        .ok_or(ClaimError::InvalidPublicKey)?;
+    let (Some(nullifier), Some(c), Some(s)) = (parse_point(&proof.nullifier), parse_scalar(&proof.c), parse_scalar(&proof.s)) else {
+        return Err(ClaimError::InvalidNullifierProof);
+    };
+    
+    // Recompute the commitments g^r = g^s / pk^c and h^r = h^s / nullifier^c
+    let scope_point = scope_point(scope, &pubkey)?;
+    let generator_commitment = ProjectivePoint::GENERATOR * s - pubkey * c;
+    let scope_commitment = scope_point * s - nullifier * c;
+    let expected = challenge([
+        &ProjectivePoint::GENERATOR, &pubkey, &scope_point, &nullifier, &generator_commitment, &scope_commitment,
+    ]);
+    if expected != c {
+        return Err(ClaimError::InvalidNullifierProof);
+    }
+    Ok(keccak_concat(&[compress(&nullifier).as_bytes()]))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::string::String;
+
+    fn pubkey_hex(signing_key: &SigningKey) -> String {
+        hex::encode(signing_key.verifying_key().to_encoded_point(false).as_bytes())
+    }
+
+    #[test]
+    fn nullifier_is_unique_per_key_and_scope() {
+        let signing_key = SigningKey::from_slice(&[1; 32]).unwrap();
+        let other_key = SigningKey::from_slice(&[2; 32]).unwrap();
+        let proof = create_nullifier_proof(&signing_key, "airdrop-1").unwrap();
+        let committed = verify_nullifier_proof(&pubkey_hex(&signing_key), "airdrop-1", &proof).unwrap();
+
+        // The key cannot produce another nullifier for the scope
+        assert_eq!(create_nullifier_proof(&signing_key, "airdrop-1"), Ok(proof.clone()));
+        let other_scope = create_nullifier_proof(&signing_key, "airdrop-2").unwrap();
+        assert_ne!(verify_nullifier_proof(&pubkey_hex(&signing_key), "airdrop-2", &other_scope), Ok(committed));
+        let foreign = create_nullifier_proof(&other_key, "airdrop-1").unwrap();
+        let forged = NullifierProof { nullifier: foreign.nullifier.clone(), ..proof.clone() };
+        assert_eq!(verify_nullifier_proof(&pubkey_hex(&signing_key), "airdrop-1", &forged), Err(ClaimError::InvalidNullifierProof));
+
+        // Nor is the proof valid for another key or scope
+        assert_eq!(verify_nullifier_proof(&pubkey_hex(&other_key), "airdrop-1", &proof), Err(ClaimError::InvalidNullifierProof));
+        assert_eq!(verify_nullifier_proof(&pubkey_hex(&signing_key), "airdrop-2", &proof), Err(ClaimError::InvalidNullifierProof));
+        let tampered = NullifierProof { s: foreign.s, ..proof };
+        assert_eq!(verify_nullifier_proof(&pubkey_hex(&signing_key), "airdrop-1", &tampered), Err(ClaimError::InvalidNullifierProof));
+    }
+}
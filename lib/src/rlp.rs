@@ -0,0 +1,174 @@
+use alloc::vec::Vec;
+use ruint::aliases::U256;
+
+use crate::error::ClaimError;
+
+// A decoded RLP item, borrowing from the encoded input
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Rlp<'a> {
+    // A byte string
+    Bytes(&'a [u8]),
+    // A list, holding the encoding of its items and its own full encoding
+    List { payload: &'a [u8], encoded: &'a [u8] },
+}
+
+impl<'a> Rlp<'a> {
+    // Decode an input that consists of exactly one RLP item
+    pub fn decode(data: &'a [u8]) -> Result<Self, ClaimError> {
+        let (item, rest) = split_item(data)?;
+        if !rest.is_empty() {
+            return Err(ClaimError::InvalidRlp);
+        }
+        Ok(item)
+    }
+
+    pub fn as_bytes(&self) -> Result<&'a [u8], ClaimError> {
+        match *self {
+            Rlp::Bytes(bytes) => Ok(bytes),
+            Rlp::List { .. } => Err(ClaimError::InvalidRlp),
+        }
+    }
+
+    // Items of a list
+    pub fn as_list(&self) -> Result<Vec<Rlp<'a>>, ClaimError> {
+        let Rlp::List { mut payload, .. } = *self else {
+            return Err(ClaimError::InvalidRlp);
+        };
+        let mut items = Vec::new();
+        while !payload.is_empty() {
+            let (item, rest) = split_item(payload)?;
+            items.push(item);
+            payload = rest;
+        }
+        Ok(items)
+    }
+
+    // A big-endian unsigned integer without leading zeros, as RLP encodes quantities
+    pub fn as_u256(&self) -> Result<U256, ClaimError> {
+        let bytes = self.as_bytes()?;
+        if bytes.first() == Some(&0) {
+            return Err(ClaimError::InvalidRlp);
+        }
+        U256::try_from_be_slice(bytes).ok_or(ClaimError::InvalidRlp)
+    }
+
+    pub fn as_u64(&self) -> Result<u64, ClaimError> {
+        u64::try_from(self.as_u256()?).map_err(|_| ClaimError::InvalidRlp)
+    }
+
+    pub fn as_bytes32(&self) -> Result<[u8; 32], ClaimError> {
+        self.as_bytes()?.try_into().map_err(|_| ClaimError::InvalidRlp)
+    }
+}
+
+// Split the first canonically encoded item off the input
+fn split_item(data: &[u8]) -> Result<(Rlp<'_>, &[u8]), ClaimError> {
+    let prefix = *data.first().ok_or(ClaimError::InvalidRlp)?;
+    let (header_len, payload_len, is_list) = match prefix {
+        0x00..=0x7f => return Ok((Rlp::Bytes(&data[..1]), &data[1..])),
+        0x80..=0xb7 => (1, usize::from(prefix - 0x80), false),
+        0xb8..=0xbf => {
+            let len_of_len = usize::from(prefix - 0xb7);
+            (1 + len_of_len, read_length(&data[1..], len_of_len)?, false)
+        }
+        0xc0..=0xf7 => (1, usize::from(prefix - 0xc0), true),
+        0xf8..=0xff => {
+            let len_of_len = usize::from(prefix - 0xf7);
+            (1 + len_of_len, read_length(&data[1..], len_of_len)?, true)
+        }
+    };
+
+    let end = header_len.checked_add(payload_len).filter(|&end| end <= data.len()).ok_or(ClaimError::InvalidRlp)?;
+    let payload = &data[header_len..end];
+    let item = if is_list {
+        Rlp::List { payload, encoded: &data[..end] }
+    } else {
+        // A single byte below 0x80 must be encoded as itself
+        if payload_len == 1 && payload[0] < 0x80 {
+            return Err(ClaimError::InvalidRlp);
+        }
+        Rlp::Bytes(payload)
+    };
+    Ok((item, &data[end..]))
+}
+
+// Read a big-endian length of `len_of_len` bytes, which must need the long form
+fn read_length(data: &[u8], len_of_len: usize) -> Result<usize, ClaimError> {
+    let bytes = data.get(..len_of_len).ok_or(ClaimError::InvalidRlp)?;
+    if bytes[0] == 0 || len_of_len > core::mem::size_of::<usize>() {
+        return Err(ClaimError::InvalidRlp);
+    }
+    let length = bytes.iter().fold(0usize, |length, &byte| (length << 8) | usize::from(byte));
+    if length < 56 {
+        return Err(ClaimError::InvalidRlp);
+    }
+    Ok(length)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::vec;
+
+    fn decode_hex(encoded: &str) -> Vec<u8> {
+        hex::decode(encoded).unwrap()
+    }
+
+    #[test]
+    fn decodes_strings_and_lists() {
+        // Examples from the RLP specification
+        assert_eq!(Rlp::decode(&decode_hex("83646f67")).unwrap(), Rlp::Bytes(b"dog"));
+        assert_eq!(Rlp::decode(&decode_hex("0f")).unwrap(), Rlp::Bytes(&[0x0f]));
+        assert_eq!(Rlp::decode(&decode_hex("80")).unwrap(), Rlp::Bytes(&[]));
+        let list = decode_hex("c88363617483646f67");
+        let items = Rlp::decode(&list).unwrap().as_list().unwrap();
+        assert_eq!(items, vec![Rlp::Bytes(b"cat"), Rlp::Bytes(b"dog")]);
+
+        let lorem = b"Lorem ipsum dolor sit amet, consectetur adipisicing elit";
+        let encoded = [&[0xb8, 0x38][..], lorem].concat();
+        assert_eq!(Rlp::decode(&encoded).unwrap(), Rlp::Bytes(lorem));
+
+        // The set theoretical representation of three: [ [], [[]], [ [], [[]] ] ]
+        let three = decode_hex("c7c0c1c0c3c0c1c0");
+        let items = Rlp::decode(&three).unwrap().as_list().unwrap();
+        assert_eq!(items.len(), 3);
+        let Rlp::List { encoded, .. } = items[2] else { panic!("expected a list") };
+        assert_eq!(encoded, &three[4..]);
+    }
+
+    #[test]
+    fn non_canonical_and_malformed_encodings_are_rejected() {
+        let invalid = [
+            // Single byte below 0x80 with a length prefix
+            "8105",
+            // Long form for a length below 56
+            "b803646f67",
+            // Length with a leading zero byte
+            "b9003800",
+            // Payload shorter than its length
+            "83646f",
+            "c88363617483646f",
+            // Trailing bytes after the item
+            "c000",
+            "",
+        ];
+        for encoded in invalid {
+            assert_eq!(Rlp::decode(&decode_hex(encoded)), Err(ClaimError::InvalidRlp), "{encoded}");
+        }
+        // List items are checked as they are split off
+        let list = decode_hex("c28105");
+        assert_eq!(Rlp::decode(&list).unwrap().as_list(), Err(ClaimError::InvalidRlp));
+    }
+
+    #[test]
+    fn integers_must_not_have_leading_zeros() {
+        assert_eq!(Rlp::decode(&decode_hex("80")).unwrap().as_u64(), Ok(0));
+        assert_eq!(Rlp::decode(&decode_hex("820400")).unwrap().as_u64(), Ok(1024));
+        assert_eq!(Rlp::decode(&decode_hex("820004")).unwrap().as_u64(), Err(ClaimError::InvalidRlp));
+        // Fits a U256 but not a u64
+        let large = decode_hex("89010000000000000000");
+        assert_eq!(Rlp::decode(&large).unwrap().as_u256(), Ok(U256::from(1u128 << 64)));
+        assert_eq!(Rlp::decode(&large).unwrap().as_u64(), Err(ClaimError::InvalidRlp));
+        assert_eq!(Rlp::decode(&decode_hex("c0")).unwrap().as_u64(), Err(ClaimError::InvalidRlp));
+    }
+}
@@ -0,0 +1,166 @@
+use alloc::format;
+use alloc::string::String;
+use k256::ecdsa::{RecoveryId, Signature, VerifyingKey};
+use sha3::{Digest, Keccak256};
+
+use crate::error::ClaimError;
+
+// Decode a 0x-prefixed signature in any of the encodings wallets produce: `r || s || v` with
+// `v` as 27/28, as a bare y-parity 0/1 or as an EIP-155 value `chain_id * 2 + 35 + parity`
+// (big-endian, more than one byte for large chain ids), or the 64-byte EIP-2098 compact
+// form `r || yParityAndS`, whose top bit is the y-parity
+pub fn decode_signature(signature: &str) -> Result<(Signature, RecoveryId), ClaimError> {
+    let sig_hex = signature.strip_prefix("0x").ok_or(ClaimError::MissingHexPrefix)?;
+    let mut sig_bytes = hex::decode(sig_hex).map_err(|_| ClaimError::InvalidHex)?;
+    
+    let y_parity = match sig_bytes.len() {
+        64 => {
+            let y_parity = sig_bytes[32] >> 7;
+            sig_bytes[32] &= 0x7f;
+            y_parity
+        }
+        65..=72 => {
+            let v = sig_bytes[64..].iter().fold(0u64, |v, byte| (v << 8) | u64::from(*byte));
+            match v {
+                0 | 1 => v as u8,
+                27 | 28 => (v - 27) as u8,
+                35.. => ((v - 35) % 2) as u8,
+                _ => return Err(ClaimError::InvalidRecoveryId),
+            }
+        }
+        _ => return Err(ClaimError::InvalidSignatureLength),
+    };
+    let recovery_id = RecoveryId::new(y_parity == 1, false);
+    let signature = Signature::try_from(&sig_bytes[..64]).map_err(|_| ClaimError::InvalidSignature)?;
+    // `(r, n - s)` with the other y-parity is also valid for the same key, so only the low-s
+    // form is accepted (EIP-2). This is checked here rather than left to the curve backend,
+    // which differs between the host and the zkVM. It only stops third parties from turning
+    // a signature into a second valid one; the signer can always sign again with a new nonce.
+    if signature.normalize_s().is_some() {
+        return Err(ClaimError::HighS);
+    }
+    
+    Ok((signature, recovery_id))
+}
+
+// Recovers a public key from a signature and message digest
+pub fn recover_pubkey_with_digest(message_digest: &[u8; 32], signature: &str) -> Result<String, ClaimError> {
+    let (signature, recovery_id) = decode_signature(signature)?;
+    
+    let recovered_key = VerifyingKey::recover_from_prehash(message_digest, &signature, recovery_id)
+        .map_err(|_| ClaimError::RecoveryFailed)?;
+    
+    Ok(hex::encode(recovered_key.to_encoded_point(false).as_bytes()))
+}
+
+// Convert a public key to an Ethereum address
+pub fn pubkey_to_address(pubkey_hex: &str) -> Result<String, ClaimError> {
+    let pubkey_bytes = hex::decode(pubkey_hex).map_err(|_| ClaimError::InvalidPublicKey)?;
+    let bytes_to_hash = match pubkey_bytes.split_first() {
+        Some((4, coordinates)) => coordinates,
+        _ => &pubkey_bytes,
+    };
+    if bytes_to_hash.len() != 64 {
+        return Err(ClaimError::InvalidPublicKey);
+    }
+    let mut hasher = Keccak256::new();
+    hasher.update(bytes_to_hash);
+    let hash = hasher.finalize();
+    Ok(format!("0x{}", hex::encode(&hash[hash.len() - 20..])))
+}
+
+// Recover the Ethereum address that produced a signature over a digest
+pub fn recover_address(message_digest: &[u8; 32], signature: &str) -> Result<String, ClaimError> {
+    let pubkey = recover_pubkey_with_digest(message_digest, signature)?;
+    pubkey_to_address(&pubkey)
+}
+
+// The malleable twin of a low-s signature: `(r, n - s)` with the other y-parity, as `r || s || v`
+// with `v` of 27 or 28. It is an equally valid ECDSA signature by the same key, which
+// `decode_signature` rejects as high-s.
+pub fn malleable_twin(signature: &str) -> Result<String, ClaimError> {
+    let (signature, recovery_id) = decode_signature(signature)?;
+    let (r, s) = signature.split_scalars();
+    let twin = Signature::from_scalars(r.to_bytes(), (-s).to_bytes()).map_err(|_| ClaimError::InvalidSignature)?;
+    let mut sig_bytes = twin.to_bytes().to_vec();
+    sig_bytes.push(if recovery_id.is_y_odd() { 27 } else { 28 });
+    Ok(format!("0x{}", hex::encode(sig_bytes)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::digest::eip191_digest;
+    use k256::ecdsa::SigningKey;
+    use ruint::aliases::U256;
+
+    const MESSAGE_DIGEST: [u8; 32] = [0x42; 32];
+
+    // Sign the test digest with a deterministic key as `r || s || v` with `v` of 27 or 28
+    fn sign(key_byte: u8) -> String {
+        let signing_key = SigningKey::from_slice(&[key_byte; 32]).unwrap();
+        let (signature, recovery_id) = signing_key.sign_prehash_recoverable(&MESSAGE_DIGEST).unwrap();
+        let mut sig_bytes = signature.to_bytes().to_vec();
+        sig_bytes.push(recovery_id.to_byte() + 27);
+        format!("0x{}", hex::encode(sig_bytes))
+    }
+
+    #[test]
+    fn signature_encodings_recover_the_same_address() {
+        // Test vectors of EIP-2098: `personal_sign` signatures by key 0x1234...1234
+        let signing_key = SigningKey::from_slice(&hex::decode("1234567890123456789012345678901234567890123456789012345678901234").unwrap()).unwrap();
+        let address = pubkey_to_address(&hex::encode(signing_key.verifying_key().to_encoded_point(false).as_bytes())).unwrap();
+        let vectors = [
+            (
+                "Hello World",
+                "0x68a020a209d3d56c46f38cc50a33f704f4a9a10a59377f8dd762ac66910e9b907e865ad05c4035ab5792787d4a0297a43617ae897930a6fe4d822b8faea520641b",
+                "0x68a020a209d3d56c46f38cc50a33f704f4a9a10a59377f8dd762ac66910e9b907e865ad05c4035ab5792787d4a0297a43617ae897930a6fe4d822b8faea52064",
+            ),
+            (
+                "It's a small(er) world",
+                "0x9328da16089fcba9bececa81663203989f2df5fe1faa6291a45381c81bd17f76139c6d6b623b42da56557e5e734a43dc83345ddfadec52cbe24d0cc64f5507931c",
+                "0x9328da16089fcba9bececa81663203989f2df5fe1faa6291a45381c81bd17f76939c6d6b623b42da56557e5e734a43dc83345ddfadec52cbe24d0cc64f550793",
+            ),
+        ];
+        for (message, signature, compact) in vectors {
+            let digest = eip191_digest(message);
+            let rs = &signature[..130];
+            let parity = u64::from(signature.ends_with("1c"));
+            let encodings = [
+                signature.to_string(),
+                compact.to_string(),
+                // Bare y-parity
+                format!("{}{:02x}", rs, parity),
+                // EIP-155 on mainnet (v = 37 or 38) and on Polygon (chain id 137, two-byte v)
+                format!("{}{:02x}", rs, 2 + 35 + parity),
+                format!("{}{:04x}", rs, 137 * 2 + 35 + parity),
+            ];
+            for encoding in &encodings {
+                assert_eq!(recover_address(&digest, encoding), Ok(address.clone()), "{}", encoding);
+                assert_eq!(decode_signature(encoding), decode_signature(signature));
+            }
+        }
+
+        let signature = sign(1);
+        assert_eq!(recover_address(&MESSAGE_DIGEST, &format!("{}1d", &signature[..130])), Err(ClaimError::InvalidRecoveryId));
+        assert_eq!(recover_address(&MESSAGE_DIGEST, &signature[..128]), Err(ClaimError::InvalidSignatureLength));
+        assert_eq!(recover_address(&MESSAGE_DIGEST, &format!("{}{}", signature, "00".repeat(8))), Err(ClaimError::InvalidSignatureLength));
+    }
+
+    #[test]
+    fn high_s_signatures_are_rejected() {
+        // The twin (r, n - s) with the other y-parity is a valid ECDSA signature for the same key
+        let signature = sign(1);
+        let sig_bytes = hex::decode(&signature[2..]).unwrap();
+        let twin = malleable_twin(&signature).unwrap();
+        let twin_bytes = hex::decode(&twin[2..]).unwrap();
+        let order = U256::from_str_radix("fffffffffffffffffffffffffffffffebaaedce6af48a03bbfd25e8cd0364141", 16).unwrap();
+        assert_eq!(twin_bytes[..32], sig_bytes[..32]);
+        assert_eq!(U256::from_be_slice(&twin_bytes[32..64]), order - U256::from_be_slice(&sig_bytes[32..64]));
+        assert_eq!(twin_bytes[64], 55 - sig_bytes[64]);
+
+        assert_eq!(recover_address(&MESSAGE_DIGEST, &twin), Err(ClaimError::HighS));
+        assert_eq!(recover_address(&MESSAGE_DIGEST, &format!("{}{:02x}", &twin[..130], 1 - (sig_bytes[64] - 27))), Err(ClaimError::HighS));
+        assert_eq!(decode_signature(&twin).map(|_| ()), Err(ClaimError::HighS));
+    }
+}
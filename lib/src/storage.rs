@@ -0,0 +1,199 @@
+use alloc::string::String;
+use alloc::vec::Vec;
+use ruint::aliases::U256;
+
+use crate::digest::{keccak_concat, safe_message_digest};
+use crate::encoding::{hex_to_address, parse_address};
+use crate::error::ClaimError;
+use crate::mpt::verify_mpt_proof;
+use crate::rlp::Rlp;
+use crate::signature::recover_address;
+use crate::types::SafeClaim;
+
+// Storage slots of a Safe (v1.0 and later): `mapping(address => address) owners`, a linked list
+// starting and ending at `SAFE_SENTINEL_OWNER`, and `uint256 threshold`
+pub const SAFE_OWNERS_SLOT: u64 = 2;
+pub const SAFE_THRESHOLD_SLOT: u64 = 4;
+pub const SAFE_SENTINEL_OWNER: &str = "0x0000000000000000000000000000000000000001";
+
+// Account fields stored in the state trie
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Account {
+    pub nonce: u64,
+    pub balance: U256,
+    pub storage_root: [u8; 32],
+    pub code_hash: [u8; 32],
+}
+
+// Decode hex proof nodes as returned by `eth_getProof`
+pub fn decode_proof_nodes(nodes: &[String]) -> Result<Vec<Vec<u8>>, ClaimError> {
+    nodes.iter()
+        .map(|node| {
+            let hex_str = node.strip_prefix("0x").unwrap_or(node);
+            hex::decode(hex_str).map_err(|_| ClaimError::InvalidStorageProof)
+        })
+        .collect()
+}
+
+// Storage slot holding `holder`'s entry in a Solidity `mapping(address => uint256)` declared
+// at `mapping_slot`: keccak256(pad32(holder) || pad32(mapping_slot))
+pub fn mapping_slot_key(holder: &str, mapping_slot: U256) -> [u8; 32] {
+    let mut padded_holder = [0u8; 32];
+    padded_holder[12..].copy_from_slice(&hex_to_address(holder));
+    keccak_concat(&[&padded_holder, &mapping_slot.to_be_bytes::<32>()])
+}
+
+// Verify an account proof against a state root, returning the account or `None` if the
+// proof shows it does not exist
+pub fn verify_account_proof(state_root: &[u8; 32], address: &str, proof: &[String]) -> Result<Option<Account>, ClaimError> {
+    let key = keccak_concat(&[&hex_to_address(address)]);
+    let Some(value) = verify_mpt_proof(state_root, &key, &decode_proof_nodes(proof)?)? else {
+        return Ok(None);
+    };
+    let fields = Rlp::decode(&value)?.as_list()?;
+    let [nonce, balance, storage_root, code_hash] = fields[..] else {
+        return Err(ClaimError::InvalidRlp);
+    };
+    Ok(Some(Account {
+        nonce: nonce.as_u64()?,
+        balance: balance.as_u256()?,
+        storage_root: storage_root.as_bytes32()?,
+        code_hash: code_hash.as_bytes32()?,
+    }))
+}
+
+// Verify a storage proof for `slot` against an account's storage root, returning the
+// stored word; slots missing from the trie hold zero
+pub fn verify_storage_proof(storage_root: &[u8; 32], slot: &[u8; 32], proof: &[String]) -> Result<U256, ClaimError> {
+    let key = keccak_concat(&[slot]);
+    match verify_mpt_proof(storage_root, &key, &decode_proof_nodes(proof)?)? {
+        Some(value) => Rlp::decode(&value)?.as_u256(),
+        None => Ok(U256::ZERO),
+    }
+}
+
+// Block header fields the program binds state proofs to
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BlockHeader {
+    // keccak256 of the RLP-encoded header, as returned by `blockhash`
+    pub hash: [u8; 32],
+    pub number: u64,
+    pub state_root: [u8; 32],
+}
+
+// Decode an RLP-encoded block header of any fork since Frontier, which all start with the
+// same fifteen fields
+pub fn decode_block_header(encoded: &[u8]) -> Result<BlockHeader, ClaimError> {
+    let fields = Rlp::decode(encoded)?.as_list()?;
+    if fields.len() < 15 {
+        return Err(ClaimError::InvalidRlp);
+    }
+    Ok(BlockHeader {
+        hash: keccak_concat(&[encoded]),
+        number: fields[8].as_u64()?,
+        state_root: fields[3].as_bytes32()?,
+    })
+}
+
+// Verify that at least `threshold` owners of a Safe signed the Safe's `SafeMessage` for
+// `message_digest` on the given chain, reading the owners and threshold from the Safe's
+// storage at the state root. As in the Safe's `checkSignatures`, signatures must be sorted
+// by ascending owner address, and an address is an owner, as in `OwnerManager.isOwner`, when
+// its `owners` entry is set and it is not the sentinel. Only ECDSA signatures of owners are
+// supported, not contract signatures, approved hashes or `eth_sign`, and every signature
+// must be an owner's, not just the first `threshold`. Returns the Safe's account.
+pub fn verify_safe_owners(
+    state_root: &[u8; 32],
+    safe_claim: &SafeClaim,
+    message_digest: &[u8; 32],
+    chain_id: u64,
+) -> Result<Account, ClaimError> {
+    let (Some(account_proof), Some(threshold_proof), Some(owner_proofs)) =
+        (&safe_claim.account_proof, &safe_claim.threshold_proof, &safe_claim.owner_proofs) else {
+        return Err(ClaimError::MissingInclusionProof);
+    };
+    if owner_proofs.len() != safe_claim.owner_signatures.len() {
+        return Err(ClaimError::MissingInclusionProof);
+    }
+    let safe = parse_address(&safe_claim.safe).ok_or(ClaimError::InvalidAddress)?;
+    let account = verify_account_proof(state_root, &safe_claim.safe, account_proof)?
+        .ok_or(ClaimError::SafeNotDeployed)?;
+    
+    let safe_digest = safe_message_digest(&safe, chain_id, message_digest);
+    let mut owners: Vec<String> = Vec::new();
+    for (signature, owner_proof) in safe_claim.owner_signatures.iter().zip(owner_proofs) {
+        // Recovered addresses are lowercase hex of one length, so they sort like numbers
+        let owner = recover_address(&safe_digest, signature)?;
+        match owners.last() {
+            Some(last) if owner == *last => return Err(ClaimError::DuplicateAddress),
+            Some(last) if owner < *last => return Err(ClaimError::UnsortedSafeOwners),
+            _ => {}
+        }
+        let owners_slot = mapping_slot_key(&owner, U256::from(SAFE_OWNERS_SLOT));
+        let next_owner = verify_storage_proof(&account.storage_root, &owners_slot, owner_proof)?;
+        if next_owner.is_zero() || owner == SAFE_SENTINEL_OWNER {
+            return Err(ClaimError::NotSafeOwner);
+        }
+        owners.push(owner);
+    }
+    
+    let threshold_slot = U256::from(SAFE_THRESHOLD_SLOT).to_be_bytes::<32>();
+    let threshold = verify_storage_proof(&account.storage_root, &threshold_slot, threshold_proof)?;
+    if threshold.is_zero() || U256::from(owners.len()) < threshold {
+        return Err(ClaimError::SafeThresholdNotMet);
+    }
+    Ok(account)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Mainnet block 11117104 (0xa9a230), a pre-London header of the fifteen original fields
+    const BLOCK_11117104: &str = "f90217a09400ec9ef59689c157ac89eeed906f15ddd768f94e1575e0e27d37c241439a5da01dcc4de8dec75d7aab85b567b6ccd41ad312451b948a7413f0a142fd40d4934794829bd824b016326a401d083b33d092293333a830a0546e330050c66d02923e7f1f3e925efaf64e4384eeecf2288f40088714a77a84a0d5eb3ad6d7c7a4798cc5fb14a6820073f44a941107c5d79dac60bd16325631fea0b21c41cbb3439c5af25304e1405524c885e733b16203221900cb7f4b387b62f0b901001f304e641097eafae088627298685d20202004a4a59e4d8900914724e2402b028c9d596660581f361240816e82d00fa14250c9ca89840887a381efa600288283d170010ab0b2a0694c81842c2482457e0eb77c2c02554614007f42aaf3b4dc15d006a83522c86a240c06d241013258d90540c3008888d576a02c10120808520a2221110f4805200302624d22092b2c0e94e849b1e1aa80bc4cc3206f00b249d0a603ee4310216850e47c8997a20aa81fe95040a49ca5a420464600e008351d161dc00d620970b6a801535c218d0b4116099292000c08001943a225d6485528828110645b8244625a182c1a88a41087e6d039b000a180d04300d0680700a15794870c40faff9c737d83a9a23083be5a6683be0fcc845f93b749967070796520e4b883e5bda9e7a59ee4bb99e9b1bc0103a0d5e2b7b71fbe4ddfe552fb2377bf7cddb16bbb7e185806036cee86994c6e97fc884722f2acd35abe0f";
+    // Mainnet block 19449567 (0x128c6df), a Cancun header with twenty fields
+    const BLOCK_19449567: &str = "f90255a090926e0298d418181bd20c23b332451e35fd7d696b5dcdc5a3a0a6b715f4c717a01dcc4de8dec75d7aab85b567b6ccd41ad312451b948a7413f0a142fd40d493479495222290dd7278aa3ddd389cc1e1d165cc4bafe5a0707875120a7103621fb4131df59904cda39de948dfda9084a1e3da44594d5404a0889a1c26dc42ba829dab552b779620feac231cde8a6c79af022bdc605c23a780a0d43aa19ecb03571d1b86d89d9bb980139d32f2f2ba59646cd5c1de9e80c68c90b90100c36919406572730518285284f2293101104140c0d42c4a786c892467868a8806f40159d29988002870403902413a1d04321320308da2e845438429e0012a00b419d8ccc8584a1c28f82a415d04eab8a5ae75c00d07761acf233414c08b6d9b571c06156086c70ea5186e9b989b0c2d55c0213c936805cd2ab331589c90194d070c00867549b1e1be14cb24500b0386cd901197c1ef5a00da453234fa48f3003dcaa894e3111c22b80e17f7d4388385a10720cda1140c0400f9e084ca34fc4870fb16b472340a2a6a63115a82522f506c06c2675080508834828c63defd06bc2331b4aa708906a06a560457b114248041e40179ebc05c6846c1e922125982f42780840128c6df8401c9c38083b0033c8465f5f4c38f6265617665726275696c642e6f7267a04c068e902990f21f92a2456fc75c59bec8be03b7f13682b6ebd27da56269beb5880000000000000000850886b221ada0360c33f20eeed5efbc7d08be46e58f8440af5db503e40908ef3d1eb314856ef78080a02843cb9f7d001bd58816a915e685ed96a555c9aeec1217736bd83a96ebd409cc";
+
+    fn bytes32(hex_str: &str) -> [u8; 32] {
+        hex::decode(hex_str).unwrap().try_into().unwrap()
+    }
+
+    #[test]
+    fn decodes_mainnet_block_headers() {
+        let cases = [
+            (BLOCK_11117104, "b25d0e54ca0104e3ebfb5a1dcdf9528140854d609886a300946fd6750dcb19f4", 11117104, "546e330050c66d02923e7f1f3e925efaf64e4384eeecf2288f40088714a77a84"),
+            (BLOCK_19449567, "85cdcbe36217fd57bf2c33731d8460657a7ce512401f49c9f6392c82a7ccf7ac", 19449567, "707875120a7103621fb4131df59904cda39de948dfda9084a1e3da44594d5404"),
+        ];
+        for (encoded, hash, number, state_root) in cases {
+            let header = decode_block_header(&hex::decode(encoded).unwrap()).unwrap();
+            assert_eq!(header, BlockHeader { hash: bytes32(hash), number, state_root: bytes32(state_root) });
+        }
+
+        // A header with trailing data does not decode
+        let mut extended = hex::decode(BLOCK_19449567).unwrap();
+        extended.push(0x80);
+        assert_eq!(decode_block_header(&extended), Err(ClaimError::InvalidRlp));
+    }
+
+    #[test]
+    fn verifies_a_real_account_proof() {
+        // `accountProof` of an `eth_getProof` response for Uniswap V3's NonfungiblePositionManager,
+        // from alloy-rpc-types-eth's test data. The response has no block, so the state root is
+        // taken as the hash of its first node.
+        let proof: Vec<String> = include_str!("../fixtures/account_proof.txt").lines().map(String::from).collect();
+        let state_root = keccak_concat(&[&decode_proof_nodes(&proof).unwrap()[0]]);
+        let account = verify_account_proof(&state_root, "0xc36442b4a4522e871399cd717abdd847ab11fe88", &proof).unwrap();
+        assert_eq!(account, Some(Account {
+            nonce: 1,
+            balance: U256::ZERO,
+            storage_root: bytes32("79fe22fe88fc4b45db10ce94d975e02e8a42b57dc190f8ae15e321f72bbc08ea"),
+            code_hash: bytes32("692e658b31cbe3407682854806658d315d61a58c7e4933a2f91d383dc00736c6"),
+        }));
+
+        // The same proof shows nothing about another account, and no longer verifies once truncated
+        let other = "0xc36442b4a4522e871399cd717abdd847ab11fe89";
+        assert_eq!(verify_account_proof(&state_root, other, &proof), Err(ClaimError::InvalidStorageProof));
+        let truncated = &proof[..proof.len() - 1];
+        assert_eq!(verify_account_proof(&state_root, "0xc36442b4a4522e871399cd717abdd847ab11fe88", truncated), Err(ClaimError::InvalidStorageProof));
+    }
+}
@@ -0,0 +1,381 @@
+use alloc::collections::BTreeMap;
+use alloc::format;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use ruint::aliases::U256;
+use serde::{Deserialize, Serialize};
+
+use crate::merkle::MAX_TREE_DEPTH;
+
+// Public inputs structure
+#[derive(Deserialize, Serialize, Debug, Default)]
+pub struct PublicInputs {
+    // Digest signed by every address; derived from `message` when that is given
+    pub message_digest: Option<String>,
+    // Plaintext message signed with `personal_sign` (EIP-191)
+    pub message: Option<String>,
+    // Structured ownership claim signed with `eth_signTypedData_v4` (EIP-712)
+    pub typed_data: Option<TypedData>,
+    pub merkle_root: String,
+    // Depth of the Merkle tree; every inclusion proof must have exactly this many nodes
+    pub tree_depth: u32,
+    // How leaves and inner nodes of the tree are hashed
+    #[serde(default)]
+    pub tree_scheme: TreeScheme,
+    // When set, only `total_balance >= min_balance` is made public
+    pub min_balance: Option<U256>,
+    // Verifier-supplied challenge the signed message must include
+    pub challenge: Option<Challenge>,
+    // Scope (e.g. an airdrop or vote id) nullifiers are derived for
+    pub nullifier_scope: Option<String>,
+    // How invalid claims are handled
+    #[serde(default)]
+    pub claim_mode: ClaimMode,
+    // Further trees, e.g. of other tokens or snapshots, that claims can belong to
+    #[serde(default)]
+    pub roots: Vec<NamedRoot>,
+    // Public weights combining the per-root totals into one aggregate balance
+    pub aggregate: Option<WeightedAggregate>,
+    // What claimed balances are proven against
+    #[serde(default)]
+    pub balance_source: BalanceSource,
+    // Hash of the block whose header the private inputs must carry; its state root must be
+    // `merkle_root`
+    pub block_hash: Option<String>,
+    // Chain the state is from; required by Safe claims, whose owners sign a message bound to
+    // the Safe's address and chain
+    pub chain_id: Option<u64>,
+}
+
+// Name claims and weights use for the top-level `merkle_root`
+pub const DEFAULT_ROOT_NAME: &str = "default";
+
+// A named Merkle tree next to the top-level one
+#[derive(Deserialize, Serialize, Debug, Clone, Default)]
+pub struct NamedRoot {
+    pub name: String,
+    pub merkle_root: String,
+    pub tree_depth: u32,
+    #[serde(default)]
+    pub tree_scheme: TreeScheme,
+    // When set, only `total >= min_balance` is made public for this root
+    pub min_balance: Option<U256>,
+}
+
+// Weighted sum of per-root totals, e.g. to value a portfolio of tokens
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub struct WeightedAggregate {
+    // Weight of each root by name; roots without a weight do not count
+    pub weights: BTreeMap<String, U256>,
+    // When set, only `aggregate >= min_total` is made public
+    pub min_total: Option<U256>,
+}
+
+impl PublicInputs {
+    // Every tree claims can belong to: the top-level root under `DEFAULT_ROOT_NAME`
+    // followed by the named roots
+    pub fn all_roots(&self) -> Vec<NamedRoot> {
+        let top_level = NamedRoot {
+            name: DEFAULT_ROOT_NAME.to_string(),
+            merkle_root: self.merkle_root.clone(),
+            tree_depth: self.tree_depth,
+            tree_scheme: self.tree_scheme,
+            min_balance: self.min_balance,
+        };
+        core::iter::once(top_level).chain(self.roots.iter().cloned()).collect()
+    }
+    
+    // Position in `all_roots` of the root a claim names; no name means the top-level root
+    pub fn root_index(&self, name: Option<&str>) -> Option<usize> {
+        match name {
+            None => Some(0),
+            Some(DEFAULT_ROOT_NAME) => Some(0),
+            Some(name) => self.roots.iter().position(|root| root.name == name).map(|index| index + 1),
+        }
+    }
+    
+    // Check that root names are unique, depths are supported, every weight names a root and
+    // state proofs have the state root as their only root
+    pub fn check_roots(&self) -> Result<(), String> {
+        if self.balance_source != BalanceSource::Snapshot {
+            if !self.roots.is_empty() {
+                return Err("Named roots require snapshot balances".to_string());
+            }
+            if self.tree_depth != 0 {
+                return Err("State proofs are checked against a state root, so the tree depth must be 0".to_string());
+            }
+        }
+        let roots = self.all_roots();
+        for (index, root) in roots.iter().enumerate() {
+            if root.tree_depth > MAX_TREE_DEPTH {
+                return Err(format!("Tree depth {} of root {:?} exceeds the maximum of {}",
+                                   root.tree_depth, root.name, MAX_TREE_DEPTH));
+            }
+            if roots[..index].iter().any(|other| other.name == root.name) {
+                return Err(format!("Root name {:?} is used more than once", root.name));
+            }
+        }
+        if let Some(aggregate) = &self.aggregate {
+            if let Some(name) = aggregate.weights.keys().find(|name| self.root_index(Some(name)).is_none()) {
+                return Err(format!("Aggregate weight names unknown root {:?}", name));
+            }
+        }
+        Ok(())
+    }
+}
+
+impl WeightedAggregate {
+    // Weighted sum of the per-root totals, given in `all_roots` order, or `None` on overflow
+    pub fn total(&self, public_inputs: &PublicInputs, totals: &[U256]) -> Option<U256> {
+        self.weights.iter().try_fold(U256::ZERO, |sum, (name, weight)| {
+            let total = totals[public_inputs.root_index(Some(name))?];
+            sum.checked_add(weight.checked_mul(total)?)
+        })
+    }
+}
+
+// What claimed balances are proven against
+#[derive(Deserialize, Serialize, Debug, Default, Clone, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum BalanceSource {
+    // Leaves of the Merkle trees in the public inputs
+    #[default]
+    Snapshot,
+    // The token contract's `mapping(address => uint256)` of balances at `balances_slot`, read
+    // through `eth_getProof` storage proofs against the state root given as `merkle_root`
+    Erc20Storage { token: String, balances_slot: U256 },
+    // The ETH balance of every claiming account, read through `eth_getProof` account proofs
+    // against the state root given as `merkle_root`
+    Native,
+}
+
+// How the program treats claims that fail verification
+#[derive(Deserialize, Serialize, Debug, Default, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ClaimMode {
+    // Abort the proof on the first invalid claim
+    #[default]
+    Strict,
+    // Skip invalid claims and commit how many were rejected
+    Lenient,
+}
+
+// Leaf encoding and node hashing of the Merkle tree
+#[derive(Deserialize, Serialize, Debug, Default, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum TreeScheme {
+    // keccak256("<lowercase address>:<decimal balance>") leaves, children ordered by the index bits
+    #[default]
+    Legacy,
+    // OpenZeppelin `StandardMerkleTree`: keccak256(keccak256(abi.encode(address, uint256)))
+    // leaves and sorted-pair hashing
+    #[serde(rename = "openzeppelin")]
+    OpenZeppelin,
+    // RFC 6962 style domain separation: keccak256(0x00 || "<lowercase address>:<decimal balance>")
+    // leaves and keccak256(0x01 || left || right) nodes, children ordered by the index bits
+    #[serde(rename = "rfc6962")]
+    Rfc6962,
+}
+
+// Verifier-supplied challenge binding a proof to one session
+#[derive(Deserialize, Serialize, Debug)]
+pub struct Challenge {
+    // 32-byte random nonce chosen by the verifier
+    pub nonce: String,
+    // Unix timestamp or block number after which the verifier rejects the proof
+    pub expires_at: u64,
+}
+
+// EIP-712 domain the typed ownership claim is signed under
+#[derive(Deserialize, Serialize, Debug)]
+pub struct Eip712Domain {
+    pub name: String,
+    pub version: String,
+    pub chain_id: u64,
+    pub verifying_contract: String,
+}
+
+// Structured claim signed by every address in EIP-712 mode
+#[derive(Deserialize, Serialize, Debug)]
+pub struct OwnershipClaim {
+    pub statement: String,
+    pub merkle_root: String,
+    pub nonce: String,
+    pub expires_at: u64,
+}
+
+// EIP-712 typed data: the domain plus the ownership claim message
+#[derive(Deserialize, Serialize, Debug)]
+pub struct TypedData {
+    pub domain: Eip712Domain,
+    pub claim: OwnershipClaim,
+}
+
+// Version of the public outputs layout, bumped whenever its shape changes
+pub const PUBLIC_OUTPUTS_VERSION: u32 = 13;
+
+// Balance statement made public by the program
+#[derive(Deserialize, Serialize, Debug)]
+pub enum BalanceClaim {
+    // The exact total balance of all verified addresses
+    Exact(U256),
+    // The total balance is at least this threshold; the program aborts otherwise,
+    // so committing this variant is itself the success marker
+    AtLeast(U256),
+}
+
+impl BalanceClaim {
+    // Whether this claim demonstrates a balance of at least `threshold`
+    pub fn meets(&self, threshold: U256) -> bool {
+        match *self {
+            BalanceClaim::Exact(total_balance) => total_balance >= threshold,
+            BalanceClaim::AtLeast(min_balance) => min_balance >= threshold,
+        }
+    }
+}
+
+// Challenge the signed message was checked to include
+#[derive(Deserialize, Serialize, Debug)]
+pub struct ChallengeCommitment {
+    pub nonce: [u8; 32],
+    pub expires_at: u64,
+}
+
+// Balance statement for one named root
+#[derive(Deserialize, Serialize, Debug)]
+pub struct RootCommitment {
+    pub name: String,
+    pub merkle_root: [u8; 32],
+    pub tree_depth: u32,
+    pub tree_scheme: TreeScheme,
+    pub balance: BalanceClaim,
+}
+
+// Weighted aggregate of the per-root totals together with the weights used
+#[derive(Deserialize, Serialize, Debug)]
+pub struct AggregateCommitment {
+    pub weights: BTreeMap<String, U256>,
+    pub balance: BalanceClaim,
+}
+
+// Block the state root was taken from
+#[derive(Deserialize, Serialize, Debug)]
+pub struct BlockCommitment {
+    pub hash: [u8; 32],
+    pub number: u64,
+}
+
+// Nullifiers of every counted address within one scope
+#[derive(Deserialize, Serialize, Debug)]
+pub struct NullifierCommitment {
+    // keccak256 of the scope string
+    pub scope_hash: [u8; 32],
+    // Sorted so their order reveals nothing about the claims
+    pub nullifiers: Vec<[u8; 32]>,
+}
+
+// Public outputs structure committed by the program
+#[derive(Deserialize, Serialize, Debug)]
+pub struct PublicOutputs {
+    pub version: u32,
+    pub merkle_root: [u8; 32],
+    pub tree_depth: u32,
+    pub tree_scheme: TreeScheme,
+    pub message_digest: [u8; 32],
+    // Balance statement for claims in the top-level tree
+    pub balance: BalanceClaim,
+    // Number of counted claims across all roots
+    pub claim_count: u32,
+    pub claim_mode: ClaimMode,
+    // Always zero in strict mode
+    pub rejected_claims: u32,
+    pub challenge: Option<ChallengeCommitment>,
+    pub nullifiers: Option<NullifierCommitment>,
+    // Balance statements for the named roots, in input order
+    pub roots: Vec<RootCommitment>,
+    pub aggregate: Option<AggregateCommitment>,
+    pub balance_source: BalanceSource,
+    // Block whose header the state root was checked against, if one was given
+    pub block: Option<BlockCommitment>,
+    // Chain Safe owners signed for, if one was given
+    pub chain_id: Option<u64>,
+}
+
+// Structure for inclusion branches in Merkle proofs
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct InclusionBranches {
+    pub index: u32,
+    pub proof: Vec<String>,
+}
+
+// Structure for a single address claim - without the address field
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct SignedMessage {
+    pub signature: String,
+    pub balance: U256,
+    // Individual Merkle proof; left out when the private inputs carry a multiproof
+    pub inclusion_branches: Option<InclusionBranches>,
+    // PLUME nullifier of the signing key for the public scope
+    pub nullifier_proof: Option<NullifierProof>,
+    // Name of the root this claim belongs to; the top-level root when absent
+    pub root: Option<String>,
+    // `eth_getProof` storage proof nodes of the address's balance slot in storage mode
+    pub storage_proof: Option<Vec<String>>,
+    // `eth_getProof` account proof nodes of the address in native ETH mode
+    pub account_proof: Option<Vec<String>>,
+}
+
+// PLUME nullifier (ERC-7524, version 1) of a key for the public scope: the point
+// sk * H(message || pk) and a proof that it uses the same secret key as the claim's signature.
+// Values are 0x-prefixed hex.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct NullifierProof {
+    // Compressed SEC1 point
+    pub nullifier: String,
+    // Challenge and response scalars of the proof
+    pub c: String,
+    pub s: String,
+}
+
+// Claim for the balance of a Safe multisig, backed by signatures of its owners instead of the
+// wallet's own signature. Proofs are `eth_getProof` nodes against the state root.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct SafeClaim {
+    pub safe: String,
+    pub balance: U256,
+    // Signatures of at least `threshold` distinct owners over the message digest
+    pub owner_signatures: Vec<String>,
+    // Account proof of the Safe, giving its storage root and, in native mode, its balance
+    pub account_proof: Option<Vec<String>>,
+    // Storage proof of the Safe's `threshold`
+    pub threshold_proof: Option<Vec<String>>,
+    // Storage proof of the Safe's `owners` entry of each signer, in signature order
+    pub owner_proofs: Option<Vec<Vec<String>>>,
+    // In storage mode, the storage proof of the Safe's balance slot in the token
+    pub storage_proof: Option<Vec<String>>,
+}
+
+// Merkle proof for several leaves at once, verified in a single pass over the tree
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MultiProof {
+    // Leaf index of every signed message, in the same order
+    pub indices: Vec<u32>,
+    // Sibling nodes that cannot be computed from the leaves, in the order they are used:
+    // level by level from the leaves up, and by ascending position within a level
+    pub proof: Vec<String>,
+}
+
+// Private inputs structure
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct PrivateInputs {
+    pub signed_messages: Vec<SignedMessage>,
+    // Proves all claims are in the tree at once, replacing their individual proofs
+    pub multiproof: Option<MultiProof>,
+    // `eth_getProof` account proof nodes of the token contract in storage mode
+    pub token_account_proof: Option<Vec<String>>,
+    // RLP-encoded header of the block whose state root the state proofs are checked against
+    pub block_header: Option<String>,
+    // Claims for Safe multisigs, counted after the signed messages
+    #[serde(default)]
+    pub safe_claims: Vec<SafeClaim>,
+}
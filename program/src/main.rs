@@ -2,129 +2,249 @@
 //! 
 //! This program proves ownership of tokens in zero-knowledge:
 //! 1. Verifies signatures prove ownership of Ethereum addresses
-//! 2. Verifies Merkle proofs show these addresses are in the token distribution
-//! 3. Computes the total balance owned without revealing which specific addresses
+//! 2. Verifies Merkle proofs of the public tree depth show these addresses are in the
+//!    token distribution, hashed with the public tree scheme (legacy, OpenZeppelin or
+//!    RFC 6962 style domain-separated)
+//! 3. Computes the total balance owned without revealing which specific addresses, per
+//!    root when claims belong to several trees, plus an optional weighted aggregate
+//! 4. Optionally proves only that the total balance meets a public threshold
+//! 5. Commits the Merkle root, tree depth and scheme, and message digest (the EIP-191 or EIP-712 hash of the
+//!    signed message when one is supplied) so the proof is bound to its public inputs
+//! 6. Optionally checks the signed message includes a verifier challenge and commits it
+//! 7. Optionally commits a per-key PLUME nullifier for a public scope for Sybil resistance
+//!
+//! Instead of a Merkle snapshot, balances can be read from Ethereum state: either from an
+//! ERC-20 token's `balances` mapping, verifying the token's account proof and one storage
+//! proof per claim, or as the native ETH balance of each claiming account, verifying one
+//! account proof per claim. All proofs are as returned by `eth_getProof` and checked
+//! against the public state root. An optional RLP-encoded block header binds that state
+//! root to a block, whose hash and number are committed. With balances from Ethereum state,
+//! Safe multisig balances can be claimed too: the Safe's owners sign instead of the wallet,
+//! and the owner set and threshold are read from the Safe's storage.
+//!
+//! In strict mode (the default) any invalid claim aborts the proof; in lenient mode invalid
+//! claims are skipped and their number is committed. Instead of one Merkle proof per claim,
+//! the private inputs may carry a single multiproof for all claims, which requires strict mode.
 
 #![no_main]
 sp1_zkvm::entrypoint!(main);
 
-use serde::{Deserialize, Serialize};
-use sha3::{Digest, Keccak256};
-use k256::{
-    ecdsa::{Signature, VerifyingKey, RecoveryId},
-};
 use std::collections::HashSet;
+use token_ownership_types::{
+    canonical_address, compute_multiproof_root, decode_block_header, eip191_digest, eip712_digest, hex_to_bytes32,
+    keccak_concat, mapping_slot_key, pubkey_to_address, recover_pubkey_with_digest,
+    verify_account_proof, verify_nullifier_proof, verify_safe_owners, verify_storage_proof, AggregateCommitment, BalanceClaim, BalanceSource, BlockCommitment,
+    ChallengeCommitment, ClaimError, ClaimMode, NullifierCommitment, PrivateInputs, PublicInputs, PublicOutputs,
+    RootCommitment, SafeClaim, SignedMessage, TreeScheme, PUBLIC_OUTPUTS_VERSION, U256,
+};
 
-// Public inputs structure
-#[derive(Deserialize, Serialize, Debug)]
-struct PublicInputs {
-    message_digest: String,
-    merkle_root: String,
+// Determine the digest every signature must cover
+fn signing_digest(public_inputs: &PublicInputs) -> [u8; 32] {
+    let derived_digest = match (&public_inputs.message, &public_inputs.typed_data) {
+        (Some(_), Some(_)) => panic!("Only one of a message and typed data may be provided"),
+        (Some(message), None) => Some(eip191_digest(message)),
+        (None, Some(typed_data)) => {
+            // The typed claim must name the tree the proof is generated against
+            assert!(hex_to_bytes32(&typed_data.claim.merkle_root) == hex_to_bytes32(&public_inputs.merkle_root),
+                    "Typed claim Merkle root does not match the public Merkle root");
+            Some(eip712_digest(typed_data))
+        }
+        (None, None) => None,
+    };
+    
+    match (derived_digest, &public_inputs.message_digest) {
+        (Some(digest), Some(expected_digest)) => {
+            assert!(digest == hex_to_bytes32(expected_digest), "Message digest does not match the hash of the signed message");
+            digest
+        }
+        (Some(digest), None) => digest,
+        (None, Some(message_digest)) => hex_to_bytes32(message_digest),
+        (None, None) => panic!("Either a message, typed data or a message digest must be provided"),
+    }
 }
 
-// Structure for inclusion branches in Merkle proofs
-#[derive(Debug, Clone, Serialize, Deserialize)]
-struct InclusionBranches {
-    pub index: u32,
-    pub proof: Vec<String>,
+// Check that the signed message includes the verifier's challenge
+fn check_challenge(public_inputs: &PublicInputs) -> Option<ChallengeCommitment> {
+    let challenge = public_inputs.challenge.as_ref()?;
+    let nonce = hex_to_bytes32(&challenge.nonce);
+    
+    if let Some(message) = &public_inputs.message {
+        // Plaintext messages must contain the challenge in its canonical text form
+        let challenge_text = format!("challenge: 0x{} expires: {}", hex::encode(nonce), challenge.expires_at);
+        assert!(message.contains(&challenge_text), "Signed message does not include the challenge");
+    } else if let Some(typed_data) = &public_inputs.typed_data {
+        assert!(hex_to_bytes32(&typed_data.claim.nonce) == nonce, "Typed claim nonce does not match the challenge");
+        assert!(typed_data.claim.expires_at == challenge.expires_at, "Typed claim expiry does not match the challenge");
+    } else {
+        panic!("A challenge requires a plaintext message or typed data to bind it to");
+    }
+    
+    Some(ChallengeCommitment { nonce, expires_at: challenge.expires_at })
 }
 
-// Structure for a single address claim - now without the address field
-#[derive(Debug, Serialize, Deserialize)]
-struct SignedMessage {
-    signature: String,
-    balance: u64,
-    inclusion_branches: InclusionBranches,
+// Check that the block header carries the public state root and matches the public block
+// hash, and determine the block it binds the proof to
+fn check_block_header(public_inputs: &PublicInputs, private_inputs: &PrivateInputs) -> Option<BlockCommitment> {
+    let Some(block_header) = &private_inputs.block_header else {
+        assert!(public_inputs.block_hash.is_none(), "A public block hash requires the block header");
+        return None;
+    };
+    assert!(public_inputs.balance_source != BalanceSource::Snapshot, "A block header requires balances from Ethereum state");
+    
+    let encoded = hex::decode(block_header.trim_start_matches("0x")).expect("Block header is not valid hex");
+    let header = decode_block_header(&encoded).unwrap_or_else(|err| panic!("Block header rejected: {}", err));
+    assert!(header.state_root == hex_to_bytes32(&public_inputs.merkle_root),
+            "Block header state root does not match the public state root");
+    if let Some(block_hash) = &public_inputs.block_hash {
+        assert!(header.hash == hex_to_bytes32(block_hash), "Block header does not hash to the public block hash");
+    }
+    
+    Some(BlockCommitment { hash: header.hash, number: header.number })
 }
 
-// Private inputs structure
-#[derive(Debug, Serialize, Deserialize)]
-struct PrivateInputs {
-    signed_messages: Vec<SignedMessage>,
+// Tree a claim's inclusion proof is checked against
+struct ClaimTree {
+    merkle_root: [u8; 32],
+    depth: u32,
+    scheme: TreeScheme,
+    // Set when balances are read from Ethereum state, where `merkle_root` is the state root
+    // and claims carry state proofs instead of Merkle proofs
+    state_balances: Option<StateBalances>,
 }
 
-// Convert a hex string to a 32-byte array
-fn hex_to_bytes32(hex: &str) -> [u8; 32] {
-    let hex_str = if hex.starts_with("0x") { &hex[2..] } else { hex };
-    let bytes = hex::decode(hex_str).unwrap();
-    
-    assert!(bytes.len() == 32, "Expected 32 bytes");
-    
-    let mut result = [0u8; 32];
-    result.copy_from_slice(&bytes);
-    result
+// Where balances are read from in Ethereum state
+enum StateBalances {
+    // The token's `balances` mapping, under the storage root from its verified account proof
+    Erc20Storage { storage_root: [u8; 32], balances_slot: U256 },
+    // The ETH balance of the claiming account
+    Native,
 }
 
-// Recovers a public key from a signature and message digest
-fn recover_pubkey_with_digest(message_digest_hex: &str, signature: &str) -> String {
-    let sig_bytes = hex::decode(&signature[2..]).unwrap();
-    let recovery_byte = sig_bytes[64];
+// What a valid claim proves
+struct VerifiedClaim {
+    // Lowercase address recovered from the signature
+    address: String,
+    // Snapshot leaf of the claim; state proofs have none
+    leaf_hash: Option<[u8; 32]>,
+    nullifier: Option<[u8; 32]>,
+}
+
+// Verify a single claim against its tree, returning what it proves or the reason it is
+// invalid. Without `check_proof` the claim's own Merkle proof is not checked, as a
+// multiproof covers all leaves after every claim is verified.
+fn verify_claim(
+    signed_message: &SignedMessage,
+    message_digest: &[u8; 32],
+    tree: &ClaimTree,
+    check_proof: bool,
+    nullifier_scope: Option<&str>,
+    seen_addresses: &HashSet<String>,
+) -> Result<VerifiedClaim, ClaimError> {
+    // Step 1: Recover the Ethereum address from the signature
+    let pubkey = recover_pubkey_with_digest(message_digest, &signed_message.signature)?;
+    let recovered_address = pubkey_to_address(&pubkey)?;
     
-    let recovery_id = RecoveryId::try_from((recovery_byte - 27) as u8).unwrap();
-    let signature = Signature::try_from(&sig_bytes[..64]).unwrap();
+    // Normalize address to lowercase for consistent comparison
+    let normalized_address = recovered_address.to_lowercase();
     
-    let message_digest = hex_to_bytes32(message_digest_hex);
+    // Step 2: Reject addresses that were already counted
+    if seen_addresses.contains(&normalized_address) {
+        return Err(ClaimError::DuplicateAddress);
+    }
     
-    let recovered_key = VerifyingKey::recover_from_prehash(&message_digest, &signature, recovery_id).unwrap();
+    let leaf_hash = match &tree.state_balances {
+        // Steps 3-5 in storage mode: read the address's balance slot from the token's storage
+        // and check it holds exactly the claimed balance
+        Some(StateBalances::Erc20Storage { storage_root, balances_slot }) => {
+            let storage_proof = signed_message.storage_proof.as_ref()
+                .ok_or(ClaimError::MissingInclusionProof)?;
+            let slot = mapping_slot_key(&recovered_address, *balances_slot);
+            if verify_storage_proof(storage_root, &slot, storage_proof)? != signed_message.balance {
+                return Err(ClaimError::StorageBalanceMismatch);
+            }
+            None
+        }
+        // Steps 3-5 in native mode: read the address's account from the state trie and check
+        // it holds exactly the claimed ETH balance; accounts missing from the trie hold none
+        Some(StateBalances::Native) => {
+            let account_proof = signed_message.account_proof.as_ref()
+                .ok_or(ClaimError::MissingInclusionProof)?;
+            let account = verify_account_proof(&tree.merkle_root, &recovered_address, account_proof)?;
+            if account.map_or(U256::ZERO, |account| account.balance) != signed_message.balance {
+                return Err(ClaimError::AccountBalanceMismatch);
+            }
+            None
+        }
+        None => {
+            // Step 3: Compute the leaf hash using the recovered address
+            let leaf_hash = tree.scheme.hash_leaf(&recovered_address, signed_message.balance);
+            
+            if check_proof {
+                // Step 4: Verify the Merkle proof, whose length must match the public tree depth
+                let inclusion_branches = signed_message.inclusion_branches.as_ref()
+                    .ok_or(ClaimError::MissingInclusionProof)?;
+                let computed_root = tree.scheme.inclusion_root(leaf_hash, inclusion_branches, tree.depth)?;
+                
+                // Step 5: Verify the computed root matches the expected root
+                if computed_root != tree.merkle_root {
+                    return Err(ClaimError::RootMismatch);
+                }
+            }
+            Some(leaf_hash)
+        }
+    };
     
-    hex::encode(recovered_key.to_encoded_point(false).as_bytes())
-}
-
-// Convert a public key to an Ethereum address
-fn pubkey_to_address(pubkey_hex: &str) -> String {
-    let pubkey_bytes = hex::decode(pubkey_hex).unwrap();
-    let bytes_to_hash = if pubkey_bytes.len() > 0 && pubkey_bytes[0] == 4 {
-        &pubkey_bytes[1..]
-    } else {
-        &pubkey_bytes
+    // Step 6: Check the key's nullifier for the scope against the recovered public key
+    let nullifier = match nullifier_scope {
+        Some(scope) => {
+            let nullifier_proof = signed_message.nullifier_proof.as_ref()
+                .ok_or(ClaimError::MissingNullifierProof)?;
+            Some(verify_nullifier_proof(&pubkey, scope, nullifier_proof)?)
+        }
+        None => None,
     };
-    let mut hasher = Keccak256::new();
-    hasher.update(bytes_to_hash);
-    let hash = hasher.finalize();
-    format!("0x{}", hex::encode(&hash[hash.len() - 20..]))
-}
-
-// Hash a leaf (address, balance) pair using keccak256
-fn hash_leaf(address: &str, balance: u64) -> [u8; 32] {
-    let address = address.to_lowercase();
-    let balance = balance.to_string();
-    let leaf_str = address + ":" + &balance;
-    
-    let mut hasher = Keccak256::new();
-    hasher.update(leaf_str.as_bytes());
-    let result = hasher.finalize();
-    
-    let mut hash = [0u8; 32];
-    hash.copy_from_slice(&result);
-    hash
+    
+    Ok(VerifiedClaim { address: normalized_address, leaf_hash, nullifier })
 }
 
-// Compute the Merkle root from a leaf hash and inclusion proof
-fn compute_inclusion_root(commitment: [u8; 32], proof: &InclusionBranches) -> [u8; 32] {
-    let bits = proof.index;
-    let mut root = commitment;
-    
-    for (i, hash_hex) in proof.proof.iter().enumerate() {
-        let hash = hex_to_bytes32(hash_hex);
-        
-        if bits & (1 << i) == 0 {
-            let mut input = [0u8; 64];
-            input[..32].copy_from_slice(&root);
-            input[32..].copy_from_slice(&hash);
-            let mut hasher = Keccak256::new();
-            hasher.update(input);
-            root.copy_from_slice(&hasher.finalize()[..32]);
-        } else {
-            let mut input = [0u8; 64];
-            input[..32].copy_from_slice(&hash);
-            input[32..].copy_from_slice(&root);
-            let mut hasher = Keccak256::new();
-            hasher.update(input);
-            root.copy_from_slice(&hasher.finalize()[..32]);
+// Verify a Safe claim against the state: enough owners signed the Safe's message for the
+// digest on the public chain, and the Safe holds exactly the claimed balance. Returns the
+// lowercase Safe address.
+fn verify_safe_claim(
+    safe_claim: &SafeClaim,
+    message_digest: &[u8; 32],
+    chain_id: u64,
+    tree: &ClaimTree,
+    seen_addresses: &HashSet<String>,
+) -> Result<String, ClaimError> {
+    // Step 1: Count each Safe once, like any other address, in the form addresses are recovered in
+    let safe_address = canonical_address(&safe_claim.safe)?;
+    if seen_addresses.contains(&safe_address) {
+        return Err(ClaimError::DuplicateAddress);
+    }
+    
+    // Step 2: Check the owners' signatures against the Safe's owner set and threshold
+    let account = verify_safe_owners(&tree.merkle_root, safe_claim, message_digest, chain_id)?;
+    
+    // Step 3: Check the Safe's balance in the token's storage or its ETH balance
+    match &tree.state_balances {
+        Some(StateBalances::Erc20Storage { storage_root, balances_slot }) => {
+            let storage_proof = safe_claim.storage_proof.as_ref()
+                .ok_or(ClaimError::MissingInclusionProof)?;
+            let slot = mapping_slot_key(&safe_address, *balances_slot);
+            if verify_storage_proof(storage_root, &slot, storage_proof)? != safe_claim.balance {
+                return Err(ClaimError::StorageBalanceMismatch);
+            }
         }
+        Some(StateBalances::Native) => {
+            if account.balance != safe_claim.balance {
+                return Err(ClaimError::AccountBalanceMismatch);
+            }
+        }
+        None => unreachable!("Safe claims are only verified with balances from Ethereum state"),
     }
     
-    root
+    Ok(safe_address)
 }
 
 pub fn main() {
@@ -132,42 +252,186 @@ pub fn main() {
     let public_inputs: PublicInputs = sp1_zkvm::io::read();
     let private_inputs: PrivateInputs = sp1_zkvm::io::read();
     
-    // Get expected Merkle root
-    let expected_merkle_root = hex_to_bytes32(&public_inputs.merkle_root);
+    // Get the trees claims can belong to, the top-level one first, and the digest every
+    // signature must cover
+    if let Err(err) = public_inputs.check_roots() {
+        panic!("{}", err);
+    }
+    let roots = public_inputs.all_roots();
+    let mut trees: Vec<ClaimTree> = roots.iter()
+        .map(|root| ClaimTree {
+            merkle_root: hex_to_bytes32(&root.merkle_root),
+            depth: root.tree_depth,
+            scheme: root.tree_scheme,
+            state_balances: None,
+        })
+        .collect();
+    
+    // With balances from Ethereum state the top-level root is a state root; in storage mode
+    // find the token's storage root in it
+    trees[0].state_balances = match &public_inputs.balance_source {
+        BalanceSource::Snapshot => None,
+        BalanceSource::Erc20Storage { token, balances_slot } => {
+            let account_proof = private_inputs.token_account_proof.as_ref()
+                .expect("Storage proofs require the token's account proof");
+            let account = verify_account_proof(&trees[0].merkle_root, token, account_proof)
+                .unwrap_or_else(|err| panic!("Token account proof rejected: {}", err))
+                .expect("Token account does not exist at the state root");
+            Some(StateBalances::Erc20Storage { storage_root: account.storage_root, balances_slot: *balances_slot })
+        }
+        BalanceSource::Native => Some(StateBalances::Native),
+    };
+    assert!(trees[0].state_balances.is_none() || private_inputs.multiproof.is_none(),
+            "Multiproofs require snapshot balances");
+    if !private_inputs.safe_claims.is_empty() {
+        assert!(trees[0].state_balances.is_some(), "Safe claims require balances from Ethereum state");
+        assert!(public_inputs.nullifier_scope.is_none(), "Safe claims cannot be combined with nullifiers");
+        assert!(public_inputs.chain_id.is_some(), "Safe claims require the chain id");
+    }
+    let message_digest = signing_digest(&public_inputs);
+    let challenge = check_challenge(&public_inputs);
+    let block = check_block_header(&public_inputs, &private_inputs);
+    let mut nullifiers = Vec::new();
+    
+    // A multiproof is checked once for all claims, so it cannot skip invalid ones
+    if let Some(multiproof) = &private_inputs.multiproof {
+        assert!(public_inputs.claim_mode == ClaimMode::Strict, "Multiproofs require strict claim mode");
+        assert!(multiproof.indices.len() == private_inputs.signed_messages.len(),
+                "Multiproof must have one leaf index per signed message");
+        assert!(private_inputs.signed_messages.iter().all(|signed_message| signed_message.root.is_none()),
+                "A multiproof only covers claims in the top-level tree");
+    }
+    let check_proofs = private_inputs.multiproof.is_none();
+    let mut leaves = Vec::new();
     
-    // Track which addresses we've already processed to prevent double-counting
-    let mut seen_addresses = HashSet::new();
+    // Track which addresses we've already processed in each tree to prevent double-counting;
+    // one address may hold balances in several trees
+    let mut seen_addresses = vec![HashSet::new(); trees.len()];
     
     // Verify all signatures and proofs
-    let mut total_balance = 0u64;
-    
-    for signed_message in &private_inputs.signed_messages {
-        // Step 1: Recover the Ethereum address from the signature
-        let pubkey = recover_pubkey_with_digest(&public_inputs.message_digest, &signed_message.signature);
-        let recovered_address = pubkey_to_address(&pubkey);
-        
-        // Normalize address to lowercase for consistent comparison
-        let normalized_address = recovered_address.to_lowercase();
-        
-        // Skip if we've already processed this address
-        if seen_addresses.contains(&normalized_address) {
-            continue;
+    let mut totals = vec![U256::ZERO; trees.len()];
+    let mut claim_count = 0u32;
+    let mut rejected_claims = 0u32;
+    
+    for (index, signed_message) in private_inputs.signed_messages.iter().enumerate() {
+        let result = public_inputs.root_index(signed_message.root.as_deref())
+            .ok_or(ClaimError::UnknownRoot)
+            .and_then(|root_index| {
+                verify_claim(signed_message, &message_digest, &trees[root_index], check_proofs,
+                             public_inputs.nullifier_scope.as_deref(), &seen_addresses[root_index])
+                    .map(|verified_claim| (root_index, verified_claim))
+            });
+        match result {
+            Ok((root_index, verified_claim)) => {
+                // Add the balance to its root's total and mark this address as seen. Overflow
+                // always aborts, even in lenient mode, since skipping the claim would hide it.
+                totals[root_index] = totals[root_index].checked_add(signed_message.balance)
+                    .unwrap_or_else(|| panic!("Claim {} rejected: {}", index, ClaimError::BalanceOverflow));
+                claim_count += 1;
+                nullifiers.extend(verified_claim.nullifier);
+                leaves.extend(verified_claim.leaf_hash);
+                seen_addresses[root_index].insert(verified_claim.address);
+            }
+            Err(err) => match public_inputs.claim_mode {
+                ClaimMode::Strict => panic!("Claim {} rejected: {}", index, err),
+                ClaimMode::Lenient => rejected_claims += 1,
+            },
         }
-        
-        // Step 3: Compute the leaf hash using the recovered address
-        let leaf_hash = hash_leaf(&recovered_address, signed_message.balance);
-        
-        // Step 4: Verify the Merkle proof
-        let computed_root = compute_inclusion_root(leaf_hash, &signed_message.inclusion_branches);
-        
-        // Step 5: Verify the computed root matches the expected root
-        if computed_root == expected_merkle_root {
-            // Step 6: Add the balance to the total and mark this address as seen
-            total_balance += signed_message.balance;
-            seen_addresses.insert(normalized_address);
+    }
+    
+    // Verify Safe claims, numbered after the signed messages; their balances belong to the
+    // top-level state root
+    for (offset, safe_claim) in private_inputs.safe_claims.iter().enumerate() {
+        let index = private_inputs.signed_messages.len() + offset;
+        let chain_id = public_inputs.chain_id.expect("Safe claims require the chain id");
+        match verify_safe_claim(safe_claim, &message_digest, chain_id, &trees[0], &seen_addresses[0]) {
+            Ok(safe_address) => {
+                totals[0] = totals[0].checked_add(safe_claim.balance)
+                    .unwrap_or_else(|| panic!("Claim {} rejected: {}", index, ClaimError::BalanceOverflow));
+                claim_count += 1;
+                seen_addresses[0].insert(safe_address);
+            }
+            Err(err) => match public_inputs.claim_mode {
+                ClaimMode::Strict => panic!("Claim {} rejected: {}", index, err),
+                ClaimMode::Lenient => rejected_claims += 1,
+            },
         }
     }
     
-    // Commit only the total balance as public output
-    sp1_zkvm::io::commit(&total_balance);
+    // Verify every leaf against the root in one pass over the shared paths
+    if let Some(multiproof) = &private_inputs.multiproof {
+        let leaves: Vec<_> = multiproof.indices.iter().copied().zip(leaves).collect();
+        let tree = &trees[0];
+        let result = compute_multiproof_root(&leaves, &multiproof.proof, tree.depth, tree.scheme)
+            .and_then(|root| if root == tree.merkle_root { Ok(()) } else { Err(ClaimError::RootMismatch) });
+        if let Err(err) = result {
+            panic!("Multiproof rejected: {}", err);
+        }
+    }
+    
+    // In threshold mode reveal only that the threshold was met, never the exact total. With an
+    // aggregate no root reveals its exact total, which together with the aggregate would give
+    // away the others, so roots without a threshold only claim at least zero.
+    let hide_totals = public_inputs.aggregate.is_some();
+    let mut balances = roots.iter().zip(&totals).map(|(root, &total_balance)| match root.min_balance {
+        Some(min_balance) => {
+            assert!(total_balance >= min_balance, "Total balance of root {:?} is below the required threshold", root.name);
+            BalanceClaim::AtLeast(min_balance)
+        }
+        None if hide_totals => BalanceClaim::AtLeast(U256::ZERO),
+        None => BalanceClaim::Exact(total_balance),
+    });
+    let balance = balances.next().expect("The top-level root is always present");
+    let root_commitments = roots[1..].iter().zip(&trees[1..]).zip(balances)
+        .map(|((root, tree), balance)| RootCommitment {
+            name: root.name.clone(),
+            merkle_root: tree.merkle_root,
+            tree_depth: tree.depth,
+            tree_scheme: tree.scheme,
+            balance,
+        })
+        .collect();
+    
+    // Combine the per-root totals with the public weights
+    let aggregate = public_inputs.aggregate.as_ref().map(|aggregate| {
+        let total = aggregate.total(&public_inputs, &totals)
+            .unwrap_or_else(|| panic!("Weighted aggregate rejected: {}", ClaimError::BalanceOverflow));
+        let balance = match aggregate.min_total {
+            Some(min_total) => {
+                assert!(total >= min_total, "Weighted aggregate is below the required threshold");
+                BalanceClaim::AtLeast(min_total)
+            }
+            None => BalanceClaim::Exact(total),
+        };
+        AggregateCommitment { weights: aggregate.weights.clone(), balance }
+    });
+    
+    // Sort nullifiers so their order does not reveal the order of the claims, and count an
+    // address that claims in several trees once
+    let nullifiers = public_inputs.nullifier_scope.as_ref().map(|scope| {
+        nullifiers.sort_unstable();
+        nullifiers.dedup();
+        NullifierCommitment { scope_hash: keccak_concat(&[scope.as_bytes()]), nullifiers }
+    });
+    
+    // Commit the balance claim together with the tree and digest it was proven against
+    let public_outputs = PublicOutputs {
+        version: PUBLIC_OUTPUTS_VERSION,
+        merkle_root: trees[0].merkle_root,
+        tree_depth: trees[0].depth,
+        tree_scheme: trees[0].scheme,
+        message_digest,
+        balance,
+        claim_count,
+        claim_mode: public_inputs.claim_mode,
+        rejected_claims,
+        challenge,
+        nullifiers,
+        roots: root_commitments,
+        aggregate,
+        balance_source: public_inputs.balance_source.clone(),
+        block,
+        chain_id: public_inputs.chain_id,
+    };
+    sp1_zkvm::io::commit(&public_outputs);
 } 
\ No newline at end of file
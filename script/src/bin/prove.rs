@@ -1,35 +1,966 @@
 use clap::{Parser, Subcommand};
-use serde::{Deserialize, Serialize};
-use sp1_sdk::{ProverClient, SP1Stdin, utils, SP1ProofWithPublicValues};
+use k256::ecdsa::SigningKey;
+use serde::{Deserialize, Serialize, Serializer};
+use sp1_sdk::{ProverClient, SP1Stdin, utils, SP1ProofWithPublicValues, SP1PublicValues};
+use std::collections::{HashMap, HashSet};
 use std::fs;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+use token_ownership_types::{
+    canonical_address, compute_multiproof_root, create_nullifier_proof, decode_block_header, eip191_digest,
+    eip712_digest, hex_to_address, hex_to_bytes32, keccak_concat, mapping_slot_key, parse_bytes32,
+    pubkey_to_address, recover_address, recover_pubkey_with_digest, safe_message_digest, verify_account_proof,
+    verify_nullifier_proof, verify_safe_owners, verify_storage_proof, BalanceClaim, BalanceSource, ClaimError,
+    ClaimMode, InclusionBranches, MerkleTree, MultiProof, NamedRoot, NullifierProof, PrivateInputs,
+    PublicInputs, PublicOutputs, SignedMessage, StandardMerkleTree, TreeScheme, DEFAULT_ROOT_NAME,
+    PUBLIC_OUTPUTS_VERSION, SAFE_OWNERS_SLOT, SAFE_THRESHOLD_SLOT, U256,
+};
 
-// Public inputs structure
-#[derive(Deserialize, Serialize, Debug)]
-struct PublicInputs {
-    message_digest: String,
-    merkle_root: String,
+// One token holder in a balance snapshot
+#[derive(Debug, Deserialize)]
+struct SnapshotEntry {
+    address: String,
+    balance: U256,
 }
 
-// Structure for inclusion branches in Merkle proofs
-#[derive(Debug, Clone, Serialize, Deserialize)]
-struct InclusionBranches {
-    pub index: u32,
-    pub proof: Vec<String>,
+// Every leaf of a built tree together with its inclusion proof, as written by `build-tree`
+#[derive(Debug, Serialize, Deserialize)]
+struct TreeDump {
+    merkle_root: String,
+    tree_depth: u32,
+    tree_scheme: TreeScheme,
+    leaves: Vec<TreeLeaf>,
 }
 
-// Structure for a single address claim - without the address field
+// A single snapshot entry and its position in the tree
 #[derive(Debug, Serialize, Deserialize)]
-struct SignedMessage {
-    signature: String,
-    balance: u64,
+struct TreeLeaf {
+    address: String,
+    #[serde(serialize_with = "serialize_decimal")]
+    balance: U256,
     inclusion_branches: InclusionBranches,
 }
 
-// Private inputs structure
-#[derive(Debug, Serialize, Deserialize)]
-struct PrivateInputs {
-    signed_messages: Vec<SignedMessage>,
+// Write balances as decimal strings, matching the leaf encoding, rather than as hex
+fn serialize_decimal<S: Serializer>(value: &U256, serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.collect_str(value)
+}
+
+// Parse a `--scheme` value using the same names as the `tree_scheme` input field
+fn parse_tree_scheme(value: &str) -> Result<TreeScheme, String> {
+    serde_json::from_value(serde_json::Value::String(value.to_string()))
+        .map_err(|_| format!("unknown tree scheme {:?}, expected `rfc6962`, `legacy` or `openzeppelin`", value))
+}
+
+// Build the tree for the given leaves in the given scheme, returning its root, depth and
+// the inclusion proof of every leaf in input order
+fn build_tree(tree_scheme: TreeScheme, leaves: Vec<[u8; 32]>) -> ([u8; 32], u32, Vec<InclusionBranches>) {
+    let count = leaves.len();
+    match tree_scheme {
+        TreeScheme::Legacy | TreeScheme::Rfc6962 => {
+            let tree = MerkleTree::new(leaves, tree_scheme);
+            (tree.root(), tree.depth(), (0..count).map(|index| tree.proof(index)).collect())
+        }
+        TreeScheme::OpenZeppelin => {
+            let tree = StandardMerkleTree::new(leaves);
+            (tree.root(), tree.depth(), (0..count).map(|index| tree.proof(index)).collect())
+        }
+    }
+}
+
+// Sign a digest with a local key as a 65-byte `r || s || v` signature with `v` of 27 or 28.
+// k256 always produces the low-s form the program requires.
+fn sign_digest(signing_key: &SigningKey, digest: &[u8; 32]) -> String {
+    let (signature, recovery_id) = signing_key.sign_prehash_recoverable(digest).expect("Failed to sign digest");
+    let mut sig_bytes = signature.to_bytes().to_vec();
+    sig_bytes.push(recovery_id.to_byte() + 27);
+    format!("0x{}", hex::encode(sig_bytes))
+}
+
+// Address of a local key, derived like the program derives it from a recovered public key
+fn signing_key_address(signing_key: &SigningKey) -> String {
+    let pubkey = signing_key.verifying_key().to_encoded_point(false);
+    pubkey_to_address(&hex::encode(pubkey.as_bytes())).expect("Public key is always 65 bytes")
+}
+
+// Parse a secp256k1 private key given as 32 bytes of hex, with or without the `0x` prefix
+fn parse_private_key(private_key: &str) -> Result<SigningKey, String> {
+    let key_bytes = hex::decode(private_key.trim().trim_start_matches("0x"))
+        .map_err(|_| "private key is not valid hex".to_string())?;
+    SigningKey::from_slice(&key_bytes).map_err(|_| "private key is not a 32-byte secp256k1 scalar".to_string())
+}
+
+// Decrypt an encrypted Ethereum JSON keystore (version 3, scrypt or PBKDF2) as written by
+// geth, Clef, MetaMask exports and `cast wallet`
+fn read_keystore(path: &Path, password: &str) -> Result<SigningKey, String> {
+    let key_bytes = eth_keystore::decrypt_key(path, password)
+        .map_err(|err| format!("cannot decrypt keystore {}: {}", path.display(), err))?;
+    SigningKey::from_slice(&key_bytes).map_err(|_| format!("keystore {} does not hold a secp256k1 key", path.display()))
+}
+
+// Sign the message digest with every key and give each signature the balance and inclusion
+// proof of the signer's leaf in a tree dump. With a nullifier scope, each key also computes its
+// nullifier for the scope with a proof. Fails for a key whose address is not in the tree.
+fn sign_claims(
+    signing_keys: &[SigningKey],
+    message_digest: &[u8; 32],
+    nullifier_scope: Option<&str>,
+    tree_dump: &TreeDump,
+    root: Option<&str>,
+) -> Result<Vec<SignedMessage>, String> {
+    signing_keys.iter()
+        .map(|signing_key| {
+            let address = signing_key_address(signing_key);
+            let leaf = tree_dump.leaves.iter()
+                .find(|leaf| leaf.address == address)
+                .ok_or_else(|| format!("address {} is not in the tree", address))?;
+            let nullifier_proof = nullifier_scope
+                .map(|scope| create_nullifier_proof(signing_key, scope))
+                .transpose()
+                .map_err(|err| format!("cannot compute the nullifier of {}: {}", address, err))?;
+            Ok(SignedMessage {
+                signature: sign_digest(signing_key, message_digest),
+                balance: leaf.balance,
+                inclusion_branches: Some(leaf.inclusion_branches.clone()),
+                nullifier_proof,
+                root: root.map(str::to_string),
+                ..Default::default()
+            })
+        })
+        .collect()
+}
+
+// A signature handed to `prepare`: bare, or together with the signer's nullifier proof
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+enum SignatureEntry {
+    Bare(String),
+    WithNullifier { signature: String, nullifier_proof: Option<NullifierProof> },
+}
+
+// Parse the signatures handed to `prepare`, either a JSON array of signatures or of
+// `{ "signature", "nullifier_proof" }` objects, or text with one signature per line.
+// Returns each signature with its nullifier proof, if any.
+fn parse_signatures(contents: &str) -> Result<Vec<(String, Option<NullifierProof>)>, String> {
+    if !contents.trim_start().starts_with('[') {
+        return Ok(contents.lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty())
+            .map(|line| (line.to_string(), None))
+            .collect());
+    }
+    let entries: Vec<SignatureEntry> = serde_json::from_str(contents).map_err(|err| err.to_string())?;
+    Ok(entries.into_iter()
+        .map(|entry| match entry {
+            SignatureEntry::Bare(signature) => (signature, None),
+            SignatureEntry::WithNullifier { signature, nullifier_proof } => (signature, nullifier_proof),
+        })
+        .collect())
+}
+
+// Recover the signer of every signature, as the program does, and give it the balance and
+// inclusion proof of the signer's leaf in a tree dump. Signers that are not in the tree or
+// were already claimed are left out with a warning; a signature that does not recover fails.
+fn prepare_signed_messages(
+    message_digest: &[u8; 32],
+    signatures: Vec<(String, Option<NullifierProof>)>,
+    tree_dump: &TreeDump,
+    root: Option<&str>,
+) -> Result<(Vec<SignedMessage>, Vec<String>), String> {
+    let leaves: HashMap<&str, &TreeLeaf> = tree_dump.leaves.iter()
+        .map(|leaf| (leaf.address.as_str(), leaf))
+        .collect();
+    let mut signed_messages = Vec::new();
+    let mut warnings = Vec::new();
+    let mut seen_addresses = HashSet::new();
+    for (index, (signature, nullifier_proof)) in signatures.into_iter().enumerate() {
+        let address = recover_address(message_digest, &signature)
+            .map_err(|err| format!("signature {}: {}", index, err))?;
+        let Some(leaf) = leaves.get(address.as_str()) else {
+            warnings.push(format!("signature {}: address {} is not in the snapshot", index, address));
+            continue;
+        };
+        if !seen_addresses.insert(address.clone()) {
+            warnings.push(format!("signature {}: address {} was already signed for", index, address));
+            continue;
+        }
+        signed_messages.push(SignedMessage {
+            signature,
+            balance: leaf.balance,
+            inclusion_branches: Some(leaf.inclusion_branches.clone()),
+            nullifier_proof,
+            root: root.map(str::to_string),
+            ..Default::default()
+        });
+    }
+    Ok((signed_messages, warnings))
+}
+
+// Read a snapshot that is either a JSON array of `{ "address", "balance" }` objects or a CSV
+// file with one `address,balance` pair per line and an optional header line
+fn read_snapshot(path: &Path) -> Vec<SnapshotEntry> {
+    let contents = fs::read_to_string(path).expect("Failed to read snapshot");
+    let mut entries: Vec<SnapshotEntry> = if path.extension().is_some_and(|ext| ext == "json") {
+        serde_json::from_str(&contents).expect("Failed to parse JSON snapshot")
+    } else {
+        contents.lines().enumerate()
+            .filter(|(_, line)| !line.trim().is_empty())
+            .filter(|(number, line)| !(*number == 0 && line.trim().to_lowercase().starts_with("address")))
+            .map(|(number, line)| {
+                let (address, balance) = line.split_once(',')
+                    .unwrap_or_else(|| panic!("Snapshot line {}: expected `address,balance`", number + 1));
+                let balance = balance.trim().parse()
+                    .unwrap_or_else(|_| panic!("Snapshot line {}: invalid balance {:?}", number + 1, balance.trim()));
+                SnapshotEntry { address: address.trim().to_string(), balance }
+            })
+            .collect()
+    };
+    
+    // Leaves hash the lowercase address, so normalize and reject anything ambiguous
+    let mut seen_addresses = HashSet::new();
+    for entry in &mut entries {
+        entry.address = entry.address.to_lowercase();
+        let is_address = entry.address.len() == 42
+            && entry.address.starts_with("0x")
+            && hex::decode(&entry.address[2..]).is_ok();
+        assert!(is_address, "Invalid address {:?} in snapshot", entry.address);
+        assert!(seen_addresses.insert(entry.address.clone()), "Duplicate address {} in snapshot", entry.address);
+    }
+    entries
+}
+
+// Response of `eth_getProof` for one account and some of its storage slots
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct EthProof {
+    address: String,
+    account_proof: Vec<String>,
+    storage_proof: Vec<EthStorageProof>,
+}
+
+// Proof of one storage slot in an `eth_getProof` response
+#[derive(Debug, Deserialize)]
+struct EthStorageProof {
+    key: String,
+    proof: Vec<String>,
+}
+
+// Read recorded `eth_getProof` responses: a bare result, a whole JSON-RPC reply, or an array
+// of either as returned for a batch request
+fn read_eth_proofs(path: &Path) -> Vec<EthProof> {
+    let response: serde_json::Value = serde_json::from_str(
+        &fs::read_to_string(path).expect("Failed to read eth_getProof response")
+    ).expect("Failed to parse eth_getProof response");
+    let responses = match response {
+        serde_json::Value::Array(responses) => responses,
+        response => vec![response],
+    };
+    responses.into_iter()
+        .map(|mut response| {
+            if let Some(result) = response.get_mut("result") {
+                response = result.take();
+            }
+            serde_json::from_value(response).expect("Failed to parse eth_getProof result")
+        })
+        .collect()
+}
+
+// Fill in the state proofs of the balance source from recorded `eth_getProof` responses: the
+// token's account proof and the storage proof of every claim's balance slot in storage mode,
+// or the account proof of every claiming address in native mode, plus the owner proofs of
+// Safe claims. Claims the responses do not cover are left without a proof.
+fn attach_eth_proofs(public_inputs: &PublicInputs, private_inputs: &mut PrivateInputs, eth_proofs: Vec<EthProof>) -> Result<(), String> {
+    let find_account = |address: &str| {
+        eth_proofs.iter().find(|eth_proof| hex_to_address(&eth_proof.address) == hex_to_address(address))
+    };
+    let message_digest = expected_message_digest(public_inputs)?;
+    match &public_inputs.balance_source {
+        BalanceSource::Snapshot => {
+            return Err("eth_getProof responses require the erc20_storage or native balance source".to_string());
+        }
+        BalanceSource::Erc20Storage { token, balances_slot } => {
+            let token_proof = find_account(token)
+                .ok_or_else(|| format!("no eth_getProof response is for the token {}", token))?;
+            for signed_message in &mut private_inputs.signed_messages {
+                // Claims with an invalid signature are reported when the inputs are validated
+                let Ok(address) = recover_address(&message_digest, &signed_message.signature) else {
+                    continue;
+                };
+                let slot = U256::from_be_bytes(mapping_slot_key(&address, *balances_slot));
+                if let Some(storage_proof) = find_slot(token_proof, slot) {
+                    signed_message.storage_proof = Some(storage_proof);
+                }
+            }
+            private_inputs.token_account_proof = Some(token_proof.account_proof.clone());
+            for safe_claim in &mut private_inputs.safe_claims {
+                let slot = U256::from_be_bytes(mapping_slot_key(&safe_claim.safe, *balances_slot));
+                if let Some(storage_proof) = find_slot(token_proof, slot) {
+                    safe_claim.storage_proof = Some(storage_proof);
+                }
+            }
+        }
+        BalanceSource::Native => {
+            for signed_message in &mut private_inputs.signed_messages {
+                let Ok(address) = recover_address(&message_digest, &signed_message.signature) else {
+                    continue;
+                };
+                if let Some(account_proof) = find_account(&address) {
+                    signed_message.account_proof = Some(account_proof.account_proof.clone());
+                }
+            }
+        }
+    }
+    
+    // A Safe's response holds its account proof and the storage proofs of its threshold and
+    // of the `owners` entries of the signers, who are recovered from the Safe's message; a
+    // missing chain id is reported when the inputs are validated
+    for safe_claim in &mut private_inputs.safe_claims {
+        let (Some(safe_proof), Some(chain_id)) = (find_account(&safe_claim.safe), public_inputs.chain_id) else {
+            continue;
+        };
+        let safe_digest = safe_message_digest(&hex_to_address(&safe_claim.safe), chain_id, &message_digest);
+        safe_claim.account_proof = Some(safe_proof.account_proof.clone());
+        safe_claim.threshold_proof = find_slot(safe_proof, U256::from(SAFE_THRESHOLD_SLOT));
+        safe_claim.owner_proofs = safe_claim.owner_signatures.iter()
+            .map(|signature| {
+                let owner = recover_address(&safe_digest, signature).ok()?;
+                find_slot(safe_proof, U256::from_be_bytes(mapping_slot_key(&owner, U256::from(SAFE_OWNERS_SLOT))))
+            })
+            .collect();
+    }
+    Ok(())
+}
+
+// Proof nodes of a storage slot in an `eth_getProof` response
+fn find_slot(eth_proof: &EthProof, slot: U256) -> Option<Vec<String>> {
+    eth_proof.storage_proof.iter()
+        .find(|storage_proof| U256::from_str_radix(storage_proof.key.trim_start_matches("0x"), 16) == Ok(slot))
+        .map(|storage_proof| storage_proof.proof.clone())
+}
+
+// Read a recorded `debug_getRawHeader` response, or a file holding just the hex-encoded header
+fn read_block_header(path: &Path) -> String {
+    let contents = fs::read_to_string(path).expect("Failed to read block header");
+    match serde_json::from_str::<serde_json::Value>(&contents) {
+        Ok(response) => response.get("result").and_then(|result| result.as_str())
+            .expect("Block header response has no hex `result`")
+            .to_string(),
+        Err(_) => contents.trim().to_string(),
+    }
+}
+
+// Check the block header in the private inputs the way the program does
+fn validate_block_header(public_inputs: &PublicInputs, private_inputs: &PrivateInputs) -> Result<(), String> {
+    let Some(block_header) = &private_inputs.block_header else {
+        return match public_inputs.block_hash {
+            Some(_) => Err("a public block hash requires the block header".to_string()),
+            None => Ok(()),
+        };
+    };
+    if public_inputs.balance_source == BalanceSource::Snapshot {
+        return Err("a block header requires balances from Ethereum state".to_string());
+    }
+    let encoded = hex::decode(block_header.trim_start_matches("0x")).map_err(|_| ClaimError::InvalidHex.to_string())?;
+    let header = decode_block_header(&encoded).map_err(|err| err.to_string())?;
+    if header.state_root != hex_to_bytes32(&public_inputs.merkle_root) {
+        return Err(format!("block {} has state root 0x{}, not {}",
+                           header.number, hex::encode(header.state_root), public_inputs.merkle_root));
+    }
+    match &public_inputs.block_hash {
+        Some(block_hash) if header.hash != hex_to_bytes32(block_hash) => {
+            Err(format!("block header hashes to 0x{}, not {}", hex::encode(header.hash), block_hash))
+        }
+        _ => Ok(()),
+    }
+}
+
+// Storage root and balances slot of the token in storage mode, from its account proof
+fn token_storage(public_inputs: &PublicInputs, private_inputs: &PrivateInputs) -> Result<Option<([u8; 32], U256)>, String> {
+    let BalanceSource::Erc20Storage { token, balances_slot } = &public_inputs.balance_source else {
+        return Ok(None);
+    };
+    let account_proof = private_inputs.token_account_proof.as_ref()
+        .ok_or_else(|| "storage proofs require the token's account proof".to_string())?;
+    match verify_account_proof(&hex_to_bytes32(&public_inputs.merkle_root), token, account_proof) {
+        Ok(Some(account)) => Ok(Some((account.storage_root, *balances_slot))),
+        Ok(None) => Err(format!("token {} does not exist at the state root", token)),
+        Err(err) => Err(err.to_string()),
+    }
+}
+
+// Determine the digest the program will check signatures against, making the same checks
+// on the public inputs as the program's `signing_digest`
+fn expected_message_digest(public_inputs: &PublicInputs) -> Result<[u8; 32], String> {
+    let derived_digest = match (&public_inputs.message, &public_inputs.typed_data) {
+        (Some(_), Some(_)) => return Err("public inputs may contain only one of a message and typed data".to_string()),
+        (Some(message), None) => Some(eip191_digest(message)),
+        (None, Some(typed_data)) => {
+            let claim_root = parse_bytes32(&typed_data.claim.merkle_root);
+            if claim_root.is_none() || claim_root != parse_bytes32(&public_inputs.merkle_root) {
+                return Err(format!("typed claim Merkle root {} does not match the public Merkle root {}",
+                                   typed_data.claim.merkle_root, public_inputs.merkle_root));
+            }
+            Some(eip712_digest(typed_data))
+        }
+        (None, None) => None,
+    };
+    let given_digest = match &public_inputs.message_digest {
+        Some(message_digest) => Some(parse_bytes32(message_digest)
+            .ok_or_else(|| format!("message digest {} is not 32 bytes of hex", message_digest))?),
+        None => None,
+    };
+    
+    match (derived_digest, given_digest) {
+        (Some(digest), Some(given_digest)) if digest != given_digest => {
+            Err(format!("message digest 0x{} does not match the hash 0x{} of the signed message",
+                        hex::encode(given_digest), hex::encode(digest)))
+        }
+        (Some(digest), _) | (None, Some(digest)) => Ok(digest),
+        (None, None) => Err("public inputs need either a message, typed data or a message digest".to_string()),
+    }
+}
+
+// Problems found in the claims, each with the index of the claim it belongs to
+type ClaimProblems = Vec<(usize, ClaimError)>;
+
+// Check every claim before handing the inputs to the program, returning each problem found
+// and the total of the claims the program would count in each root, in `all_roots` order.
+// Fails only if the public inputs do not determine the message digest.
+fn validate_private_inputs(public_inputs: &PublicInputs, private_inputs: &PrivateInputs) -> Result<(ClaimProblems, Vec<U256>), String> {
+    let message_digest = expected_message_digest(public_inputs)?;
+    let roots = public_inputs.all_roots();
+    // A bad account proof is reported by `report_invalid_claims` as a whole
+    let token_storage = token_storage(public_inputs, private_inputs);
+    
+    let mut problems = Vec::new();
+    let mut seen_addresses = vec![HashSet::new(); roots.len()];
+    let mut totals = vec![U256::ZERO; roots.len()];
+    for (index, signed_message) in private_inputs.signed_messages.iter().enumerate() {
+        let problems_before = problems.len();
+        let Some(root_index) = public_inputs.root_index(signed_message.root.as_deref()) else {
+            problems.push((index, ClaimError::UnknownRoot));
+            continue;
+        };
+        let root = &roots[root_index];
+        let recovered = recover_pubkey_with_digest(&message_digest, &signed_message.signature)
+            .and_then(|pubkey| Ok((pubkey_to_address(&pubkey)?, pubkey)));
+        let (address, pubkey) = match recovered {
+            Ok(recovered) => recovered,
+            Err(err) => {
+                problems.push((index, err));
+                continue;
+            }
+        };
+        if !seen_addresses[root_index].insert(address.clone()) {
+            problems.push((index, ClaimError::DuplicateAddress));
+        }
+        // A multiproof is checked as a whole by `validate_multiproof`
+        let leaf_hash = root.tree_scheme.hash_leaf(&address, signed_message.balance);
+        match (&public_inputs.balance_source, &private_inputs.multiproof, &signed_message.inclusion_branches) {
+            (BalanceSource::Erc20Storage { .. }, _, _) => {
+                if let Ok(Some((storage_root, balances_slot))) = &token_storage {
+                    let slot = mapping_slot_key(&address, *balances_slot);
+                    let stored_balance = signed_message.storage_proof.as_ref()
+                        .ok_or(ClaimError::MissingInclusionProof)
+                        .and_then(|storage_proof| verify_storage_proof(storage_root, &slot, storage_proof));
+                    match stored_balance {
+                        Ok(balance) if balance != signed_message.balance => {
+                            problems.push((index, ClaimError::StorageBalanceMismatch))
+                        }
+                        Ok(_) => {}
+                        Err(err) => problems.push((index, err)),
+                    }
+                }
+            }
+            (BalanceSource::Native, _, _) => {
+                let eth_balance = signed_message.account_proof.as_ref()
+                    .ok_or(ClaimError::MissingInclusionProof)
+                    .and_then(|account_proof| verify_account_proof(&hex_to_bytes32(&root.merkle_root), &address, account_proof))
+                    .map(|account| account.map_or(U256::ZERO, |account| account.balance));
+                match eth_balance {
+                    Ok(balance) if balance != signed_message.balance => {
+                        problems.push((index, ClaimError::AccountBalanceMismatch))
+                    }
+                    Ok(_) => {}
+                    Err(err) => problems.push((index, err)),
+                }
+            }
+            (BalanceSource::Snapshot, Some(_), _) => {}
+            (BalanceSource::Snapshot, None, Some(inclusion_branches)) => {
+                match root.tree_scheme.inclusion_root(leaf_hash, inclusion_branches, root.tree_depth) {
+                    Ok(computed_root) if computed_root != hex_to_bytes32(&root.merkle_root) => {
+                        problems.push((index, ClaimError::RootMismatch))
+                    }
+                    Ok(_) => {}
+                    Err(err) => problems.push((index, err)),
+                }
+            }
+            (BalanceSource::Snapshot, None, None) => problems.push((index, ClaimError::MissingInclusionProof)),
+        }
+        if let Some(scope) = &public_inputs.nullifier_scope {
+            let nullifier = signed_message.nullifier_proof.as_ref()
+                .ok_or(ClaimError::MissingNullifierProof)
+                .and_then(|nullifier_proof| verify_nullifier_proof(&pubkey, scope, nullifier_proof));
+            if let Err(err) = nullifier {
+                problems.push((index, err));
+            }
+        }
+        // Only claims the program would count contribute to their root's total
+        if problems.len() == problems_before {
+            match totals[root_index].checked_add(signed_message.balance) {
+                Some(sum) => totals[root_index] = sum,
+                None => problems.push((index, ClaimError::BalanceOverflow)),
+            }
+        }
+    }
+    
+    // Safe claims are numbered after the signed messages and count towards the state root; a
+    // missing chain id is reported by `validate_safe_claims` as a whole
+    let chain_id = public_inputs.chain_id.unwrap_or_default();
+    let safe_claims = if public_inputs.chain_id.is_some() { &private_inputs.safe_claims[..] } else { &[] };
+    for (offset, safe_claim) in safe_claims.iter().enumerate() {
+        let index = private_inputs.signed_messages.len() + offset;
+        let safe_address = match canonical_address(&safe_claim.safe) {
+            Ok(safe_address) => safe_address,
+            Err(err) => {
+                problems.push((index, err));
+                continue;
+            }
+        };
+        if !seen_addresses[0].insert(safe_address.clone()) {
+            problems.push((index, ClaimError::DuplicateAddress));
+            continue;
+        }
+        let account = match verify_safe_owners(&hex_to_bytes32(&public_inputs.merkle_root), safe_claim, &message_digest, chain_id) {
+            Ok(account) => account,
+            Err(err) => {
+                problems.push((index, err));
+                continue;
+            }
+        };
+        let balance_problem = match &public_inputs.balance_source {
+            BalanceSource::Erc20Storage { .. } => match &token_storage {
+                Ok(Some((storage_root, balances_slot))) => {
+                    let slot = mapping_slot_key(&safe_address, *balances_slot);
+                    match safe_claim.storage_proof.as_ref()
+                        .ok_or(ClaimError::MissingInclusionProof)
+                        .and_then(|storage_proof| verify_storage_proof(storage_root, &slot, storage_proof)) {
+                        Ok(balance) if balance != safe_claim.balance => Some(ClaimError::StorageBalanceMismatch),
+                        Ok(_) => None,
+                        Err(err) => Some(err),
+                    }
+                }
+                _ => None,
+            },
+            BalanceSource::Native if account.balance != safe_claim.balance => Some(ClaimError::AccountBalanceMismatch),
+            _ => None,
+        };
+        match balance_problem {
+            Some(err) => problems.push((index, err)),
+            None => match totals[0].checked_add(safe_claim.balance) {
+                Some(sum) => totals[0] = sum,
+                None => problems.push((index, ClaimError::BalanceOverflow)),
+            },
+        }
+    }
+    Ok((problems, totals))
+}
+
+// Check that Safe claims are used where the program supports them
+fn validate_safe_claims(public_inputs: &PublicInputs, private_inputs: &PrivateInputs) -> Result<(), String> {
+    if private_inputs.safe_claims.is_empty() {
+        return Ok(());
+    }
+    if public_inputs.balance_source == BalanceSource::Snapshot {
+        return Err("Safe claims require balances from Ethereum state".to_string());
+    }
+    if public_inputs.nullifier_scope.is_some() {
+        return Err("Safe claims cannot be combined with nullifiers".to_string());
+    }
+    if public_inputs.chain_id.is_none() {
+        return Err("Safe claims require the chain id, which their owners sign for".to_string());
+    }
+    Ok(())
+}
+
+// Check that the weighted aggregate of the per-root totals of the claims the program would
+// count fits in 256 bits and meets its public threshold
+fn validate_aggregate(public_inputs: &PublicInputs, totals: &[U256]) -> Result<(), String> {
+    let Some(aggregate) = &public_inputs.aggregate else {
+        return Ok(());
+    };
+    let total = aggregate.total(public_inputs, totals).ok_or_else(|| ClaimError::BalanceOverflow.to_string())?;
+    match aggregate.min_total {
+        Some(min_total) if total < min_total => Err(format!("weighted aggregate {} is below {}", total, min_total)),
+        _ => Ok(()),
+    }
+}
+
+// Check a multiproof supplied in the private inputs against the leaves of all claims
+fn validate_multiproof(public_inputs: &PublicInputs, private_inputs: &PrivateInputs) -> Result<(), String> {
+    let Some(multiproof) = &private_inputs.multiproof else {
+        return Ok(());
+    };
+    if public_inputs.claim_mode != ClaimMode::Strict {
+        return Err("multiproofs require strict claim mode".to_string());
+    }
+    if public_inputs.balance_source != BalanceSource::Snapshot {
+        return Err("multiproofs require snapshot balances".to_string());
+    }
+    if public_inputs.tree_scheme == TreeScheme::OpenZeppelin {
+        return Err("multiproofs are not supported for OpenZeppelin trees".to_string());
+    }
+    if private_inputs.signed_messages.iter().any(|signed_message| signed_message.root.is_some()) {
+        return Err("a multiproof only covers claims in the top-level tree".to_string());
+    }
+    if multiproof.indices.len() != private_inputs.signed_messages.len() {
+        return Err(format!("multiproof has {} leaf indices for {} signed messages",
+                           multiproof.indices.len(), private_inputs.signed_messages.len()));
+    }
+    
+    let message_digest = expected_message_digest(public_inputs)?;
+    let mut leaves = Vec::new();
+    for (signed_message, &index) in private_inputs.signed_messages.iter().zip(&multiproof.indices) {
+        // Claims whose signature is invalid are already reported individually
+        let Ok(address) = recover_address(&message_digest, &signed_message.signature) else {
+            return Ok(());
+        };
+        leaves.push((index, public_inputs.tree_scheme.hash_leaf(&address, signed_message.balance)));
+    }
+    match compute_multiproof_root(&leaves, &multiproof.proof, public_inputs.tree_depth, public_inputs.tree_scheme) {
+        Ok(root) if root != hex_to_bytes32(&public_inputs.merkle_root) => Err(ClaimError::RootMismatch.to_string()),
+        Ok(_) => Ok(()),
+        Err(err) => Err(err.to_string()),
+    }
+}
+
+// Replace the claims' individual Merkle proofs with one multiproof when the program can
+// use it: in strict mode, for an index-ordered tree, with every claim in the top-level tree
+// carrying its own proof
+fn merge_inclusion_proofs(public_inputs: &PublicInputs, private_inputs: &mut PrivateInputs) {
+    let mergeable = private_inputs.multiproof.is_none()
+        && private_inputs.signed_messages.len() > 1
+        && public_inputs.claim_mode == ClaimMode::Strict
+        && public_inputs.balance_source == BalanceSource::Snapshot
+        && public_inputs.tree_scheme != TreeScheme::OpenZeppelin
+        && private_inputs.signed_messages.iter()
+            .all(|signed_message| signed_message.inclusion_branches.is_some() && signed_message.root.is_none());
+    if !mergeable {
+        return;
+    }
+    
+    let proofs: Vec<InclusionBranches> = private_inputs.signed_messages.iter_mut()
+        .filter_map(|signed_message| signed_message.inclusion_branches.take())
+        .collect();
+    let multiproof = MultiProof::from_proofs(&proofs);
+    println!("Merged {} Merkle proofs into a multiproof with {} of their {} nodes",
+             proofs.len(), multiproof.proof.len(), proofs.iter().map(|proof| proof.proof.len()).sum::<usize>());
+    private_inputs.multiproof = Some(multiproof);
+}
+
+// Report claim problems found on the host; in strict mode the program would abort on
+// the first of them, so stop before spending time executing or proving
+fn report_invalid_claims(public_inputs: &PublicInputs, private_inputs: &PrivateInputs) {
+    if let Err(err) = public_inputs.check_roots() {
+        eprintln!("{}", err);
+        std::process::exit(1);
+    }
+    // The program aborts on a bad multiproof, token account proof, block header or misplaced
+    // Safe claims whatever the claim mode
+    if let Err(err) = validate_multiproof(public_inputs, private_inputs) {
+        eprintln!("Invalid multiproof: {}", err);
+        std::process::exit(1);
+    }
+    if let Err(err) = token_storage(public_inputs, private_inputs) {
+        eprintln!("Invalid token account proof: {}", err);
+        std::process::exit(1);
+    }
+    if let Err(err) = validate_block_header(public_inputs, private_inputs) {
+        eprintln!("Invalid block header: {}", err);
+        std::process::exit(1);
+    }
+    if let Err(err) = validate_safe_claims(public_inputs, private_inputs) {
+        eprintln!("Invalid Safe claims: {}", err);
+        std::process::exit(1);
+    }
+    let (problems, totals) = match validate_private_inputs(public_inputs, private_inputs) {
+        Ok(checked) => checked,
+        Err(err) => {
+            eprintln!("Invalid public inputs: {}", err);
+            std::process::exit(1);
+        }
+    };
+    if !problems.is_empty() {
+        eprintln!("Found {} problem(s) in the private inputs:", problems.len());
+        for (index, err) in &problems {
+            eprintln!("  Claim {}: {}", index, err);
+        }
+        // Overflow aborts the program even in lenient mode
+        let overflows = problems.iter().any(|(_, err)| *err == ClaimError::BalanceOverflow);
+        if public_inputs.claim_mode == ClaimMode::Strict || overflows {
+            std::process::exit(1);
+        }
+        eprintln!("Continuing in lenient mode; invalid claims will be skipped");
+    }
+    // The program also aborts on a bad aggregate whatever the claim mode
+    if let Err(err) = validate_aggregate(public_inputs, &totals) {
+        eprintln!("Invalid weighted aggregate: {}", err);
+        std::process::exit(1);
+    }
+}
+
+// Decode the public outputs committed by the program, rejecting unknown layouts
+fn read_public_outputs(public_values: &mut SP1PublicValues) -> PublicOutputs {
+    let public_outputs: PublicOutputs = public_values.read();
+    assert_eq!(public_outputs.version, PUBLIC_OUTPUTS_VERSION,
+               "Unsupported public outputs version {}", public_outputs.version);
+    public_outputs
+}
+
+// Check that the proof was generated against the expected Merkle root, message digest and
+// challenge, and that it demonstrates at least the expected threshold when one is given
+fn check_public_outputs(public_outputs: &PublicOutputs, public_inputs: &PublicInputs) -> Result<(), String> {
+    if public_outputs.merkle_root != hex_to_bytes32(&public_inputs.merkle_root) {
+        return Err(format!("Merkle root mismatch: proof committed 0x{}, expected {}",
+                           hex::encode(public_outputs.merkle_root), public_inputs.merkle_root));
+    }
+    if public_outputs.tree_depth != public_inputs.tree_depth {
+        return Err(format!("Tree depth mismatch: proof committed {}, expected {}",
+                           public_outputs.tree_depth, public_inputs.tree_depth));
+    }
+    if public_outputs.tree_scheme != public_inputs.tree_scheme {
+        return Err(format!("Tree scheme mismatch: proof was verified with the {:?} scheme, expected {:?}",
+                           public_outputs.tree_scheme, public_inputs.tree_scheme));
+    }
+    let message_digest = expected_message_digest(public_inputs)?;
+    if public_outputs.message_digest != message_digest {
+        return Err(format!("Message digest mismatch: proof committed 0x{}, expected 0x{}",
+                           hex::encode(public_outputs.message_digest), hex::encode(message_digest)));
+    }
+    if public_outputs.balance_source != public_inputs.balance_source {
+        return Err(format!("Balance source mismatch: proof committed {:?}, expected {:?}",
+                           public_outputs.balance_source, public_inputs.balance_source));
+    }
+    if let Some(block_hash) = &public_inputs.block_hash {
+        let committed = public_outputs.block.as_ref()
+            .ok_or_else(|| "Proof does not commit to a block".to_string())?;
+        if committed.hash != hex_to_bytes32(block_hash) {
+            return Err(format!("Block mismatch: proof committed block 0x{}, expected {}",
+                               hex::encode(committed.hash), block_hash));
+        }
+    }
+    if public_outputs.chain_id != public_inputs.chain_id {
+        return Err(format!("Chain id mismatch: proof committed {:?}, expected {:?}",
+                           public_outputs.chain_id, public_inputs.chain_id));
+    }
+    if public_outputs.claim_mode != public_inputs.claim_mode {
+        return Err(format!("Claim mode mismatch: proof was generated in {:?} mode, expected {:?}",
+                           public_outputs.claim_mode, public_inputs.claim_mode));
+    }
+    if let Some(challenge) = &public_inputs.challenge {
+        let committed = public_outputs.challenge.as_ref()
+            .ok_or_else(|| "Proof does not commit to a challenge".to_string())?;
+        if committed.nonce != hex_to_bytes32(&challenge.nonce) || committed.expires_at != challenge.expires_at {
+            return Err(format!("Challenge mismatch: proof committed nonce 0x{} expiring at {}, expected {} expiring at {}",
+                               hex::encode(committed.nonce), committed.expires_at, challenge.nonce, challenge.expires_at));
+        }
+    }
+    if let Some(scope) = &public_inputs.nullifier_scope {
+        let committed = public_outputs.nullifiers.as_ref()
+            .ok_or_else(|| "Proof does not commit to nullifiers".to_string())?;
+        if committed.scope_hash != keccak_concat(&[scope.as_bytes()]) {
+            return Err(format!("Nullifier scope mismatch: proof was not generated for scope {:?}", scope));
+        }
+    }
+    if let Some(threshold) = public_inputs.min_balance {
+        if !public_outputs.balance.meets(threshold) {
+            return Err(format!("Balance claim {:?} does not meet the threshold {}",
+                               public_outputs.balance, threshold));
+        }
+    }
+    if public_outputs.roots.len() != public_inputs.roots.len() {
+        return Err(format!("Root count mismatch: proof committed {} named roots, expected {}",
+                           public_outputs.roots.len(), public_inputs.roots.len()));
+    }
+    for (committed, root) in public_outputs.roots.iter().zip(&public_inputs.roots) {
+        if committed.name != root.name || committed.merkle_root != hex_to_bytes32(&root.merkle_root)
+            || committed.tree_depth != root.tree_depth || committed.tree_scheme != root.tree_scheme {
+            return Err(format!("Root mismatch: proof committed {:?} 0x{} (depth {}, {:?}), expected {:?} {} (depth {}, {:?})",
+                               committed.name, hex::encode(committed.merkle_root), committed.tree_depth, committed.tree_scheme,
+                               root.name, root.merkle_root, root.tree_depth, root.tree_scheme));
+        }
+        if let Some(threshold) = root.min_balance {
+            if !committed.balance.meets(threshold) {
+                return Err(format!("Balance claim {:?} for root {:?} does not meet the threshold {}",
+                                   committed.balance, root.name, threshold));
+            }
+        }
+    }
+    if let Some(aggregate) = &public_inputs.aggregate {
+        let committed = public_outputs.aggregate.as_ref()
+            .ok_or_else(|| "Proof does not commit to a weighted aggregate".to_string())?;
+        if committed.weights != aggregate.weights {
+            return Err(format!("Aggregate weights mismatch: proof committed {:?}, expected {:?}",
+                               committed.weights, aggregate.weights));
+        }
+        if let Some(threshold) = aggregate.min_total {
+            if !committed.balance.meets(threshold) {
+                return Err(format!("Weighted aggregate {:?} does not meet the threshold {}",
+                                   committed.balance, threshold));
+            }
+        }
+    }
+    Ok(())
+}
+
+// Check that the proof commits to the expected challenge nonce and that the challenge
+// has not expired, comparing against `current_block` or else the current Unix time
+fn check_challenge(public_outputs: &PublicOutputs, expected_nonce: &str, current_block: Option<u64>) -> Result<(), String> {
+    let challenge = public_outputs.challenge.as_ref()
+        .ok_or_else(|| "Proof does not commit to a challenge".to_string())?;
+    if challenge.nonce != hex_to_bytes32(expected_nonce) {
+        return Err(format!("Challenge mismatch: proof committed nonce 0x{}, expected {}",
+                           hex::encode(challenge.nonce), expected_nonce));
+    }
+    let now = current_block.unwrap_or_else(|| {
+        SystemTime::now().duration_since(UNIX_EPOCH).expect("System clock before Unix epoch").as_secs()
+    });
+    if now > challenge.expires_at {
+        return Err(format!("Challenge expired at {} (now {})", challenge.expires_at, now));
+    }
+    Ok(())
+}
+
+// Reject proofs that reuse a nullifier recorded in the spent-nullifier file, then record
+// the proof's nullifiers there so a second proof for the same addresses is rejected
+fn check_and_record_nullifiers(public_outputs: &PublicOutputs, spent_file: &Path) -> Result<(), String> {
+    let committed = public_outputs.nullifiers.as_ref()
+        .ok_or_else(|| "Proof does not commit to nullifiers".to_string())?;
+    // Only a missing file means nothing was spent yet; any other error must not turn off
+    // replay detection
+    let mut spent: Vec<String> = match fs::read_to_string(spent_file) {
+        Ok(contents) => serde_json::from_str(&contents)
+            .map_err(|err| format!("Failed to parse spent nullifiers {}: {}", spent_file.display(), err))?,
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => Vec::new(),
+        Err(err) => return Err(format!("Failed to read spent nullifiers {}: {}", spent_file.display(), err)),
+    };
+    for nullifier in &committed.nullifiers {
+        let nullifier = format!("0x{}", hex::encode(nullifier));
+        if spent.contains(&nullifier) {
+            return Err(format!("Nullifier {} has already been used", nullifier));
+        }
+        spent.push(nullifier);
+    }
+    fs::write(spent_file, serde_json::to_string_pretty(&spent).unwrap())
+        .map_err(|err| format!("Failed to write spent nullifiers {}: {}", spent_file.display(), err))
+}
+
+// Print the decoded public outputs
+fn print_public_outputs(public_outputs: &PublicOutputs) {
+    println!("Merkle Root: 0x{}", hex::encode(public_outputs.merkle_root));
+    println!("Tree Depth: {}", public_outputs.tree_depth);
+    println!("Tree Scheme: {:?}", public_outputs.tree_scheme);
+    match &public_outputs.balance_source {
+        BalanceSource::Snapshot => {}
+        BalanceSource::Erc20Storage { token, balances_slot } => {
+            println!("Balances From: storage of token {} (balances slot {}) at the state root above", token, balances_slot);
+        }
+        BalanceSource::Native => println!("Balances From: ETH balances of the claiming accounts at the state root above"),
+    }
+    if let Some(block) = &public_outputs.block {
+        println!("Block: {} (hash 0x{})", block.number, hex::encode(block.hash));
+    }
+    if let Some(chain_id) = public_outputs.chain_id {
+        println!("Chain Id: {}", chain_id);
+    }
+    println!("Message Digest: 0x{}", hex::encode(public_outputs.message_digest));
+    match public_outputs.balance {
+        BalanceClaim::Exact(total_balance) => println!("Verified Total Balance: {}", total_balance),
+        BalanceClaim::AtLeast(min_balance) => println!("Verified Minimum Balance: >= {}", min_balance),
+    }
+    println!("Verified Claims: {}", public_outputs.claim_count);
+    if public_outputs.claim_mode == ClaimMode::Lenient {
+        println!("Rejected Claims: {}", public_outputs.rejected_claims);
+    }
+    if let Some(challenge) = &public_outputs.challenge {
+        println!("Challenge: 0x{} (expires at {})", hex::encode(challenge.nonce), challenge.expires_at);
+    }
+    if let Some(nullifiers) = &public_outputs.nullifiers {
+        println!("Nullifier Scope Hash: 0x{}", hex::encode(nullifiers.scope_hash));
+        for nullifier in &nullifiers.nullifiers {
+            println!("Nullifier: 0x{}", hex::encode(nullifier));
+        }
+    }
+    for root in &public_outputs.roots {
+        println!("Root {:?}: 0x{} (depth {}, {:?})", root.name, hex::encode(root.merkle_root), root.tree_depth, root.tree_scheme);
+        match root.balance {
+            BalanceClaim::Exact(total_balance) => println!("  Verified Total Balance: {}", total_balance),
+            BalanceClaim::AtLeast(min_balance) => println!("  Verified Minimum Balance: >= {}", min_balance),
+        }
+    }
+    if let Some(aggregate) = &public_outputs.aggregate {
+        for (name, weight) in &aggregate.weights {
+            println!("Aggregate Weight {:?}: {}", name, weight);
+        }
+        match aggregate.balance {
+            BalanceClaim::Exact(total) => println!("Verified Weighted Aggregate: {}", total),
+            BalanceClaim::AtLeast(min_total) => println!("Verified Minimum Weighted Aggregate: >= {}", min_total),
+        }
+    }
+}
+
+// Read the public inputs, apply a `--threshold` override and print what the proof is bound to
+fn load_public_inputs(public_file: &Path, threshold: Option<U256>) -> Result<PublicInputs, String> {
+    let contents = fs::read_to_string(public_file)
+        .map_err(|err| format!("Failed to read public inputs {}: {}", public_file.display(), err))?;
+    let mut public_inputs: PublicInputs = serde_json::from_str(&contents)
+        .map_err(|err| format!("Failed to parse public inputs {}: {}", public_file.display(), err))?;
+    if threshold.is_some() {
+        public_inputs.min_balance = threshold;
+    }
+    
+    let message_digest = expected_message_digest(&public_inputs)
+        .map_err(|err| format!("Invalid public inputs {}: {}", public_file.display(), err))?;
+    println!("Public inputs: Message digest: 0x{}, Merkle root: {}", 
+             hex::encode(message_digest), public_inputs.merkle_root);
+    if let Some(message) = &public_inputs.message {
+        println!("Signed message: {:?}", message);
+    }
+    if let Some(typed_data) = &public_inputs.typed_data {
+        println!("Signed typed claim: {:?} (domain {:?} v{}, chain {})", typed_data.claim.statement,
+                 typed_data.domain.name, typed_data.domain.version, typed_data.domain.chain_id);
+    }
+    Ok(public_inputs)
+}
+
+// Read the public and private inputs for `execute` and `prove`: attach the recorded state
+// proofs and block header, check every claim as the program will and merge the inclusion
+// proofs into a multiproof where the program can use one
+fn load_inputs(
+    public_file: &Path,
+    private_file: &Path,
+    threshold: Option<U256>,
+    eth_proof: &[PathBuf],
+    block_header: Option<&Path>,
+) -> Result<(PublicInputs, PrivateInputs), String> {
+    let public_inputs = load_public_inputs(public_file, threshold)?;
+    let contents = fs::read_to_string(private_file)
+        .map_err(|err| format!("Failed to read private inputs {}: {}", private_file.display(), err))?;
+    let mut private_inputs: PrivateInputs = serde_json::from_str(&contents)
+        .map_err(|err| format!("Failed to parse private inputs {}: {}", private_file.display(), err))?;
+    if !eth_proof.is_empty() {
+        let eth_proofs = eth_proof.iter().flat_map(|path| read_eth_proofs(path)).collect();
+        attach_eth_proofs(&public_inputs, &mut private_inputs, eth_proofs)
+            .map_err(|err| format!("Invalid eth_getProof response: {}", err))?;
+    }
+    if let Some(block_header) = block_header {
+        private_inputs.block_header = Some(read_block_header(block_header));
+    }
+    
+    println!("Private inputs: {} signed messages", private_inputs.signed_messages.len());
+    report_invalid_claims(&public_inputs, &private_inputs);
+    merge_inclusion_proofs(&public_inputs, &mut private_inputs);
+    Ok((public_inputs, private_inputs))
 }
 
 #[derive(Parser)]
@@ -48,6 +979,18 @@ enum Commands {
         
         #[arg(short = 'r', long, default_value = "../data_1/private_inputs.json")]
         private_file: PathBuf,
+        
+        /// Only reveal that the total balance is at least this amount
+        #[arg(short, long)]
+        threshold: Option<U256>,
+        
+        /// Recorded `eth_getProof` responses supplying the account and storage proofs; may be repeated
+        #[arg(long)]
+        eth_proof: Vec<PathBuf>,
+        
+        /// Recorded `debug_getRawHeader` response, or hex RLP, of the block the state root is taken from
+        #[arg(long)]
+        block_header: Option<PathBuf>,
     },
     /// Generate a proof of token ownership
     Prove {
@@ -63,6 +1006,18 @@ enum Commands {
         
         #[arg(short, long)]
         groth16: bool,
+        
+        /// Only reveal that the total balance is at least this amount
+        #[arg(short, long)]
+        threshold: Option<U256>,
+        
+        /// Recorded `eth_getProof` responses supplying the account and storage proofs; may be repeated
+        #[arg(long)]
+        eth_proof: Vec<PathBuf>,
+        
+        /// Recorded `debug_getRawHeader` response, or hex RLP, of the block the state root is taken from
+        #[arg(long)]
+        block_header: Option<PathBuf>,
     },
     /// Verify a previously generated proof
     Verify {
@@ -72,12 +1027,114 @@ enum Commands {
         
         #[arg(short = 'u', long, default_value = "../data_1/public_inputs.json")]
         public_file: PathBuf,
+        
+        /// Reject the proof unless it demonstrates a balance of at least this amount
+        #[arg(short, long)]
+        threshold: Option<U256>,
+        
+        /// Reject the proof unless it commits to this challenge nonce and the challenge has not expired
+        #[arg(short, long)]
+        expect_challenge: Option<String>,
+        
+        /// Check challenge expiry against this block number instead of the current Unix time
+        #[arg(short, long, requires = "expect_challenge")]
+        current_block: Option<u64>,
+        
+        /// JSON list of already used nullifiers; the proof is rejected if it reuses one, otherwise its nullifiers are added
+        #[arg(short, long)]
+        spent_nullifiers: Option<PathBuf>,
+    },
+    /// Build the Merkle tree from an address,balance snapshot and write its root and proofs
+    BuildTree {
+        /// Snapshot of holders: a CSV with `address,balance` lines, or a JSON array of objects
+        #[arg(short, long)]
+        snapshot: PathBuf,
+        
+        /// Public inputs file to store the Merkle root in; other fields and roots are kept if it exists
+        #[arg(short = 'u', long, default_value = "../data_1/public_inputs.json")]
+        public_file: PathBuf,
+        
+        /// Output file for every address's balance and inclusion proof
+        #[arg(short, long, default_value = "../data_1/tree_proofs.json")]
+        tree_file: PathBuf,
+        
+        /// Leaf and node hashing: `rfc6962` (domain-separated), `legacy` or `openzeppelin` (StandardMerkleTree)
+        #[arg(long, default_value = "rfc6962", value_parser = parse_tree_scheme)]
+        scheme: TreeScheme,
+        
+        /// Store the tree as the named root with this name instead of the top-level root
+        #[arg(long)]
+        root_name: Option<String>,
+    },
+    /// Sign a message with local keys and write the signed messages with their inclusion proofs
+    Sign {
+        /// Public inputs whose message, typed data or digest and nullifier scope are signed; cannot be combined with `--message` or `--message-digest`
+        #[arg(short = 'u', long, default_value = "../data_1/public_inputs.json", conflicts_with_all = ["message", "message_digest"])]
+        public_file: PathBuf,
+        
+        /// Plaintext message to sign like `personal_sign` (EIP-191)
+        #[arg(short, long, conflicts_with = "message_digest")]
+        message: Option<String>,
+        
+        /// 32-byte hex digest to sign as is
+        #[arg(short = 'd', long)]
+        message_digest: Option<String>,
+        
+        /// secp256k1 private key in hex; may be repeated
+        #[arg(short = 'k', long)]
+        private_key: Vec<String>,
+        
+        /// Encrypted Ethereum JSON keystore; may be repeated
+        #[arg(long)]
+        keystore: Vec<PathBuf>,
+        
+        /// File holding the keystores' password; defaults to the `KEYSTORE_PASSWORD` environment variable
+        #[arg(long)]
+        password_file: Option<PathBuf>,
+        
+        /// Tree proofs written by `build-tree`
+        #[arg(short, long, default_value = "../data_1/tree_proofs.json")]
+        tree_file: PathBuf,
+        
+        /// Named root the tree is stored as in the public inputs
+        #[arg(long)]
+        root_name: Option<String>,
+        
+        /// Output file for the signed messages
+        #[arg(short, long, default_value = "../data_1/signed_messages.json")]
+        output: PathBuf,
+    },
+    /// Assemble the private inputs from signatures and the tree proofs of their signers
+    Prepare {
+        /// Public inputs whose message, typed data or digest the signatures are over
+        #[arg(short = 'u', long, default_value = "../data_1/public_inputs.json")]
+        public_file: PathBuf,
+        
+        /// Signatures: a JSON array, of strings or of `{ "signature", "nullifier_proof" }` objects, or one signature per line
+        #[arg(short, long)]
+        signatures: PathBuf,
+        
+        /// Tree proofs written by `build-tree`
+        #[arg(short, long, default_value = "../data_1/tree_proofs.json")]
+        tree_file: PathBuf,
+        
+        /// Named root the tree is stored as in the public inputs
+        #[arg(long)]
+        root_name: Option<String>,
+        
+        /// Private inputs file to write
+        #[arg(short = 'r', long, default_value = "../data_1/private_inputs.json")]
+        private_file: PathBuf,
     },
     /// Inspect the public values in a proof without verification
     Inspect {
         /// Path to the binary proof file to inspect
         #[arg(short, long)]
         proof_file: PathBuf,
+        
+        /// Report whether the proof claims a balance of at least this amount
+        #[arg(short, long)]
+        threshold: Option<U256>,
     },
 }
 
@@ -88,7 +1145,7 @@ fn main() {
     let cli = Cli::parse();
     
     match &cli.command {
-        Commands::Execute { public_file, private_file } => {
+        Commands::Execute { public_file, private_file, threshold, eth_proof, block_header } => {
             println!("Executing token ownership verification program...");
             
             // Get the ELF file
@@ -100,17 +1157,13 @@ fn main() {
             let client = ProverClient::from_env();
             
             // Read input files
-            let public_inputs: PublicInputs = serde_json::from_str(
-                &fs::read_to_string(public_file).expect("Failed to read public inputs")
-            ).expect("Failed to parse public inputs");
-            
-            let private_inputs: PrivateInputs = serde_json::from_str(
-                &fs::read_to_string(private_file).expect("Failed to read private inputs")
-            ).expect("Failed to parse private inputs");
-            
-            println!("Public inputs: Message digest: {}, Merkle root: {}", 
-                     public_inputs.message_digest, public_inputs.merkle_root);
-            println!("Private inputs: {} signed messages", private_inputs.signed_messages.len());
+            let (public_inputs, private_inputs) = match load_inputs(public_file, private_file, *threshold, eth_proof, block_header.as_deref()) {
+                Ok(inputs) => inputs,
+                Err(err) => {
+                    eprintln!("{}", err);
+                    std::process::exit(1);
+                }
+            };
             
             // Create program input
             let mut stdin = SP1Stdin::new();
@@ -122,14 +1175,14 @@ fn main() {
                 .run()
                 .expect("Execution failed");
             
-            // Read public outputs (just the total balance)
-            let total_balance: u64 = public_values.read();
+            // Read public outputs
+            let public_outputs = read_public_outputs(&mut public_values);
             
             println!("\n=== Execution Results ===");
-            println!("Verified Total Balance: {}", total_balance);
+            print_public_outputs(&public_outputs);
             println!("Cycles used: {}", execution_report.total_instruction_count());
         },
-        Commands::Prove { public_file, private_file, output, groth16 } => {
+        Commands::Prove { public_file, private_file, output, groth16, threshold, eth_proof, block_header } => {
             println!("Generating token ownership proof...");
             
             // Get the ELF file
@@ -141,17 +1194,13 @@ fn main() {
             let client = ProverClient::from_env();
             
             // Read input files
-            let public_inputs: PublicInputs = serde_json::from_str(
-                &fs::read_to_string(public_file).expect("Failed to read public inputs")
-            ).expect("Failed to parse public inputs");
-            
-            let private_inputs: PrivateInputs = serde_json::from_str(
-                &fs::read_to_string(private_file).expect("Failed to read private inputs")
-            ).expect("Failed to parse private inputs");
-            
-            println!("Public inputs: Message digest: {}, Merkle root: {}", 
-                     public_inputs.message_digest, public_inputs.merkle_root);
-            println!("Private inputs: {} signed messages", private_inputs.signed_messages.len());
+            let (public_inputs, private_inputs) = match load_inputs(public_file, private_file, *threshold, eth_proof, block_header.as_deref()) {
+                Ok(inputs) => inputs,
+                Err(err) => {
+                    eprintln!("{}", err);
+                    std::process::exit(1);
+                }
+            };
             
             // Create program input
             let mut stdin = SP1Stdin::new();
@@ -172,7 +1221,7 @@ fn main() {
             
             // Read public outputs
             let mut public_values = proof.public_values.clone();
-            let total_balance: u64 = public_values.read();
+            let public_outputs = read_public_outputs(&mut public_values);
             
             // Verify the proof
             println!("Verifying proof...");
@@ -182,7 +1231,7 @@ fn main() {
             proof.save(output).expect("Failed to save proof");
             
             println!("\n=== Proof Successfully Generated and Verified ===");
-            println!("Verified Total Balance: {}", total_balance);
+            print_public_outputs(&public_outputs);
             println!("Proof saved to: {} (binary file)", output.display());
             
             if *groth16 {
@@ -191,7 +1240,7 @@ fn main() {
                 println!("Proof: 0x{}", hex::encode(proof.bytes()));
             }
         },
-        Commands::Verify { proof_file, public_file } => {
+        Commands::Verify { proof_file, public_file, threshold, expect_challenge, current_block, spent_nullifiers } => {
             println!("Verifying token ownership proof...");
             
             // Get the ELF file
@@ -205,13 +1254,14 @@ fn main() {
             // Load the proof (binary format)
             let proof = SP1ProofWithPublicValues::load(proof_file).expect("Failed to load proof");
             
-            // Read public inputs file (optional, just for display)
-            let public_inputs: PublicInputs = serde_json::from_str(
-                &fs::read_to_string(public_file).expect("Failed to read public inputs")
-            ).expect("Failed to parse public inputs");
-            
-            println!("Public inputs: Message digest: {}, Merkle root: {}", 
-                     public_inputs.message_digest, public_inputs.merkle_root);
+            // Read public inputs file the proof must be bound to
+            let public_inputs = match load_public_inputs(public_file, *threshold) {
+                Ok(public_inputs) => public_inputs,
+                Err(err) => {
+                    eprintln!("{}", err);
+                    std::process::exit(1);
+                }
+            };
             
             // Setup the verification key
             let (_, vk) = client.setup(&elf);
@@ -219,14 +1269,247 @@ fn main() {
             // Verify the proof
             client.verify(&proof, &vk).expect("Proof verification failed");
             
-            // Read public outputs
+            // Read public outputs and check they match the supplied public inputs
             let mut public_values = proof.public_values.clone();
-            let total_balance: u64 = public_values.read();
+            let public_outputs = read_public_outputs(&mut public_values);
+            let mut result = check_public_outputs(&public_outputs, &public_inputs);
+            if let (Ok(()), Some(expected_nonce)) = (&result, expect_challenge) {
+                result = check_challenge(&public_outputs, expected_nonce, *current_block);
+            }
+            if let (Ok(()), Some(spent_file)) = (&result, spent_nullifiers) {
+                result = check_and_record_nullifiers(&public_outputs, spent_file);
+            }
+            if let Err(err) = result {
+                eprintln!("Proof rejected: {}", err);
+                std::process::exit(1);
+            }
             
             println!("\n=== Proof Successfully Verified ===");
-            println!("Verified Total Balance: {}", total_balance);
+            print_public_outputs(&public_outputs);
+        },
+        Commands::BuildTree { snapshot, public_file, tree_file, scheme, root_name } => {
+            println!("Building Merkle tree from snapshot...");
+            
+            let entries = read_snapshot(snapshot);
+            if entries.is_empty() {
+                eprintln!("Snapshot has no entries");
+                std::process::exit(1);
+            }
+            
+            // Legacy and RFC 6962 trees keep the snapshot order and are padded up to a power
+            // of two; OpenZeppelin trees sort the leaves by hash like `StandardMerkleTree.of`
+            let leaves = entries.iter().map(|entry| scheme.hash_leaf(&entry.address, entry.balance)).collect();
+            let (root, tree_depth, proofs) = build_tree(*scheme, leaves);
+            let merkle_root = hex::encode(root);
+            
+            // Store the tree in the public inputs, keeping message and other settings
+            let mut public_inputs: PublicInputs = match fs::read_to_string(public_file) {
+                Ok(contents) => match serde_json::from_str(&contents) {
+                    Ok(public_inputs) => public_inputs,
+                    Err(err) => {
+                        eprintln!("Failed to parse public inputs {}: {}", public_file.display(), err);
+                        std::process::exit(1);
+                    }
+                },
+                // Only a missing file starts new public inputs
+                Err(err) if err.kind() == std::io::ErrorKind::NotFound => PublicInputs::default(),
+                Err(err) => {
+                    eprintln!("Failed to read public inputs {}: {}", public_file.display(), err);
+                    std::process::exit(1);
+                }
+            };
+            match root_name.as_deref().filter(|name| *name != DEFAULT_ROOT_NAME) {
+                Some(name) => {
+                    let index = match public_inputs.roots.iter().position(|root| root.name == name) {
+                        Some(index) => index,
+                        None => {
+                            public_inputs.roots.push(NamedRoot { name: name.to_string(), ..Default::default() });
+                            public_inputs.roots.len() - 1
+                        }
+                    };
+                    let root = &mut public_inputs.roots[index];
+                    root.merkle_root = merkle_root.clone();
+                    root.tree_depth = tree_depth;
+                    root.tree_scheme = *scheme;
+                }
+                None => {
+                    public_inputs.merkle_root = merkle_root.clone();
+                    public_inputs.tree_depth = tree_depth;
+                    public_inputs.tree_scheme = *scheme;
+                }
+            }
+            fs::write(public_file, serde_json::to_string_pretty(&public_inputs).unwrap())
+                .expect("Failed to write public inputs");
+            
+            // Write every leaf with its inclusion proof
+            let tree_dump = TreeDump {
+                merkle_root: merkle_root.clone(),
+                tree_depth,
+                tree_scheme: *scheme,
+                leaves: entries.into_iter().zip(proofs)
+                    .map(|(entry, inclusion_branches)| TreeLeaf {
+                        address: entry.address,
+                        balance: entry.balance,
+                        inclusion_branches,
+                    })
+                    .collect(),
+            };
+            fs::write(tree_file, serde_json::to_string_pretty(&tree_dump).unwrap())
+                .expect("Failed to write tree proofs");
+            
+            println!("\n=== Merkle Tree Built ===");
+            println!("Leaves: {}", tree_dump.leaves.len());
+            println!("Depth: {}", tree_depth);
+            println!("Scheme: {:?}", scheme);
+            println!("Merkle Root: {}", merkle_root);
+            println!("Public inputs written to: {}", public_file.display());
+            println!("Inclusion proofs written to: {}", tree_file.display());
+        },
+        Commands::Sign { public_file, message, message_digest, private_key, keystore, password_file, tree_file, root_name, output } => {
+            println!("Signing with {} keys...", private_key.len() + keystore.len());
+            
+            // Sign the given message or digest, or else what the public inputs expect
+            let (digest, nullifier_scope) = match (message, message_digest) {
+                (Some(message), _) => (eip191_digest(message), None),
+                (None, Some(message_digest)) => (hex_to_bytes32(message_digest), None),
+                (None, None) => {
+                    let public_inputs: PublicInputs = serde_json::from_str(
+                        &fs::read_to_string(public_file).expect("Failed to read public inputs")
+                    ).expect("Failed to parse public inputs");
+                    match expected_message_digest(&public_inputs) {
+                        Ok(digest) => (digest, public_inputs.nullifier_scope),
+                        Err(err) => {
+                            eprintln!("Invalid public inputs: {}", err);
+                            std::process::exit(1);
+                        }
+                    }
+                }
+            };
+            
+            let mut signing_keys = Vec::new();
+            for private_key in private_key {
+                match parse_private_key(private_key) {
+                    Ok(signing_key) => signing_keys.push(signing_key),
+                    Err(err) => {
+                        eprintln!("Invalid private key: {}", err);
+                        std::process::exit(1);
+                    }
+                }
+            }
+            if !keystore.is_empty() {
+                let password = match password_file {
+                    Some(path) => fs::read_to_string(path).expect("Failed to read password file").trim_end_matches(['\r', '\n']).to_string(),
+                    None => std::env::var("KEYSTORE_PASSWORD").expect("Keystores need --password-file or KEYSTORE_PASSWORD"),
+                };
+                for path in keystore {
+                    match read_keystore(path, &password) {
+                        Ok(signing_key) => signing_keys.push(signing_key),
+                        Err(err) => {
+                            eprintln!("Invalid keystore: {}", err);
+                            std::process::exit(1);
+                        }
+                    }
+                }
+            }
+            if signing_keys.is_empty() {
+                eprintln!("Give at least one --private-key or --keystore");
+                std::process::exit(1);
+            }
+            
+            let tree_dump: TreeDump = serde_json::from_str(
+                &fs::read_to_string(tree_file).expect("Failed to read tree proofs")
+            ).expect("Failed to parse tree proofs");
+            let signed_messages = match sign_claims(&signing_keys, &digest, nullifier_scope.as_deref(), &tree_dump, root_name.as_deref()) {
+                Ok(signed_messages) => signed_messages,
+                Err(err) => {
+                    eprintln!("Cannot sign: {}", err);
+                    std::process::exit(1);
+                }
+            };
+            fs::write(output, serde_json::to_string_pretty(&signed_messages).unwrap())
+                .expect("Failed to write signed messages");
+            
+            println!("\n=== Messages Signed ===");
+            println!("Message digest: 0x{}", hex::encode(digest));
+            for (signing_key, signed_message) in signing_keys.iter().zip(&signed_messages) {
+                println!("{}: balance {}, leaf {}", signing_key_address(signing_key), signed_message.balance,
+                         signed_message.inclusion_branches.as_ref().map_or(0, |branches| branches.index));
+            }
+            if nullifier_scope.is_some() {
+                println!("Nullifiers computed as well");
+            }
+            println!("Signed messages written to: {}", output.display());
+        },
+        Commands::Prepare { public_file, signatures, tree_file, root_name, private_file } => {
+            println!("Preparing private inputs...");
+            
+            let public_inputs: PublicInputs = serde_json::from_str(
+                &fs::read_to_string(public_file).expect("Failed to read public inputs")
+            ).expect("Failed to parse public inputs");
+            let tree_dump: TreeDump = serde_json::from_str(
+                &fs::read_to_string(tree_file).expect("Failed to read tree proofs")
+            ).expect("Failed to parse tree proofs");
+            let signatures = match parse_signatures(&fs::read_to_string(signatures).expect("Failed to read signatures")) {
+                Ok(signatures) => signatures,
+                Err(err) => {
+                    eprintln!("Invalid signatures file: {}", err);
+                    std::process::exit(1);
+                }
+            };
+            
+            // The tree must be the one the public inputs commit to under that name
+            let Some(root_index) = public_inputs.root_index(root_name.as_deref()) else {
+                eprintln!("Public inputs have no root named {:?}", root_name.as_deref().unwrap_or_default());
+                std::process::exit(1);
+            };
+            let root = &public_inputs.all_roots()[root_index];
+            if !root.merkle_root.trim_start_matches("0x").eq_ignore_ascii_case(tree_dump.merkle_root.trim_start_matches("0x")) {
+                eprintln!("The tree root {} differs from the root {} in the public inputs", tree_dump.merkle_root, root.merkle_root);
+                std::process::exit(1);
+            }
+            
+            let message_digest = match expected_message_digest(&public_inputs) {
+                Ok(digest) => digest,
+                Err(err) => {
+                    eprintln!("Invalid public inputs: {}", err);
+                    std::process::exit(1);
+                }
+            };
+            let (signed_messages, warnings) = match prepare_signed_messages(&message_digest, signatures, &tree_dump, root_name.as_deref()) {
+                Ok(prepared) => prepared,
+                Err(err) => {
+                    eprintln!("Invalid signature: {}", err);
+                    std::process::exit(1);
+                }
+            };
+            for warning in &warnings {
+                eprintln!("Warning: {}", warning);
+            }
+            let private_inputs = PrivateInputs { signed_messages, ..Default::default() };
+            fs::write(private_file, serde_json::to_string_pretty(&private_inputs).unwrap())
+                .expect("Failed to write private inputs");
+            
+            // Report what the program would still reject, such as missing nullifier proofs
+            // The message digest was checked above
+            let (problems, _) = validate_private_inputs(&public_inputs, &private_inputs).unwrap_or_default();
+            for (index, err) in problems {
+                eprintln!("Warning: claim {}: {}", index, err);
+            }
+            
+            println!("\n=== Private Inputs Prepared ===");
+            println!("Claims: {} ({} signatures left out)", private_inputs.signed_messages.len(), warnings.len());
+            let total = private_inputs.signed_messages.iter()
+                .try_fold(U256::ZERO, |total, signed_message| total.checked_add(signed_message.balance));
+            match total {
+                Some(total) => println!("Total balance: {}", total),
+                None => eprintln!("The total balance overflows, so the program would abort on these claims"),
+            }
+            println!("Private inputs written to: {}", private_file.display());
+            if total.is_none() {
+                std::process::exit(1);
+            }
         },
-        Commands::Inspect { proof_file } => {
+        Commands::Inspect { proof_file, threshold } => {
             println!("Inspecting proof public values...");
             
             // Load the proof (binary format)
@@ -234,12 +1517,495 @@ fn main() {
             
             // Read public outputs
             let mut public_values = proof.public_values.clone();
-            let total_balance: u64 = public_values.read();
+            let public_outputs = read_public_outputs(&mut public_values);
             
             println!("\n=== Proof Public Values ===");
-            println!("Total Balance: {}", total_balance);
+            print_public_outputs(&public_outputs);
+            if let Some(threshold) = threshold {
+                println!("Meets Threshold {}: {}", threshold, public_outputs.balance.meets(*threshold));
+            }
             println!("Proof Size: {} bytes", proof.bytes().len());
             println!("Raw Public Values (hex): 0x{}", hex::encode(proof.public_values.to_vec()));
         },
     }
-} 
\ No newline at end of file
+} 
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sp1_sdk::include_elf;
+    use token_ownership_types::{
+        hash_leaf, malleable_twin, Eip712Domain, OwnershipClaim, SafeClaim, TypedData, WeightedAggregate,
+    };
+
+    const ELF: &[u8] = include_elf!("token-ownership-program");
+    const MESSAGE_DIGEST: [u8; 32] = [0x42; 32];
+    // State root of the synthetic state the `eth_getProof` fixtures were recorded from
+    const STATE_ROOT: &str = "0x9f05f17326dfc35119f9ea4d490f77c20519105710de9f0f5771bdea5a45496b";
+    // Chain the fixture Safe's owners sign for
+    const CHAIN_ID: u64 = 1;
+
+    // Sign the test digest with a deterministic key and return the signature and signer address
+    fn sign(key_byte: u8) -> (String, String) {
+        let signing_key = SigningKey::from_slice(&[key_byte; 32]).unwrap();
+        (sign_digest(&signing_key, &MESSAGE_DIGEST), signing_key_address(&signing_key))
+    }
+
+    // Build inputs for a two-leaf tree holding the given balances
+    fn two_leaf_inputs(balances: [U256; 2]) -> (PublicInputs, PrivateInputs) {
+        let claims = [sign(1), sign(2)];
+        let leaves = [hash_leaf(&claims[0].1, balances[0]), hash_leaf(&claims[1].1, balances[1])];
+        let merkle_root = keccak_concat(&[&leaves[0], &leaves[1]]);
+
+        let signed_messages = (0..2)
+            .map(|i| SignedMessage {
+                signature: claims[i].0.clone(),
+                balance: balances[i],
+                inclusion_branches: Some(InclusionBranches {
+                    index: i as u32,
+                    proof: vec![hex::encode(leaves[1 - i])],
+                }),
+                ..Default::default()
+            })
+            .collect();
+        let public_inputs = PublicInputs {
+            message_digest: Some(format!("0x{}", hex::encode(MESSAGE_DIGEST))),
+            merkle_root: hex::encode(merkle_root),
+            tree_depth: 1,
+            tree_scheme: TreeScheme::Legacy,
+            ..Default::default()
+        };
+        (public_inputs, PrivateInputs { signed_messages, ..Default::default() })
+    }
+
+    fn execute(public_inputs: &PublicInputs, private_inputs: &PrivateInputs) -> Result<PublicOutputs, String> {
+        let mut stdin = SP1Stdin::new();
+        stdin.write(public_inputs);
+        stdin.write(private_inputs);
+        let (mut public_values, _) = ProverClient::from_env()
+            .execute(ELF, &stdin)
+            .run()
+            .map_err(|err| err.to_string())?;
+        Ok(read_public_outputs(&mut public_values))
+    }
+
+    // Build the tree dump `build-tree` writes for a two-leaf tree of keys 1 and 2, and public
+    // inputs for it
+    fn two_leaf_tree_dump() -> (PublicInputs, TreeDump) {
+        let addresses = [sign(1).1, sign(2).1];
+        let balances = [U256::from(1000), U256::from(1500)];
+        let leaves = addresses.iter().zip(balances).map(|(address, balance)| TreeScheme::Rfc6962.hash_leaf(address, balance)).collect();
+        let (root, tree_depth, proofs) = build_tree(TreeScheme::Rfc6962, leaves);
+        let tree_dump = TreeDump {
+            merkle_root: hex::encode(root),
+            tree_depth,
+            tree_scheme: TreeScheme::Rfc6962,
+            leaves: addresses.into_iter().zip(balances).zip(proofs)
+                .map(|((address, balance), inclusion_branches)| TreeLeaf { address, balance, inclusion_branches })
+                .collect(),
+        };
+        let public_inputs = PublicInputs {
+            message_digest: Some(format!("0x{}", hex::encode(MESSAGE_DIGEST))),
+            merkle_root: hex::encode(root),
+            tree_depth,
+            tree_scheme: TreeScheme::Rfc6962,
+            ..Default::default()
+        };
+        // Read the dump back as the commands do from the file
+        (public_inputs, serde_json::from_str(&serde_json::to_string(&tree_dump).unwrap()).unwrap())
+    }
+
+    #[test]
+    fn sign_fills_signed_messages_from_tree_dump() {
+        // The fixture keystore holds the test key 0x0101...01
+        let keystore = Path::new(concat!(env!("CARGO_MANIFEST_DIR"), "/fixtures/keystore.json"));
+        assert!(read_keystore(keystore, "wrong password").is_err());
+        let signing_keys = [
+            read_keystore(keystore, "fixture password").unwrap(),
+            parse_private_key(&format!("0x{}", "02".repeat(32))).unwrap(),
+        ];
+
+        let (mut public_inputs, tree_dump) = two_leaf_tree_dump();
+        public_inputs.nullifier_scope = Some("test scope".to_string());
+        let signed_messages = sign_claims(&signing_keys, &MESSAGE_DIGEST, Some("test scope"), &tree_dump, None).unwrap();
+        assert_eq!(signed_messages[0].signature, sign(1).0);
+        let private_inputs = PrivateInputs { signed_messages, ..Default::default() };
+        assert!(validate_private_inputs(&public_inputs, &private_inputs).unwrap().0.is_empty());
+
+        // Key 3 has no leaf in the tree
+        let absent_key = parse_private_key(&"03".repeat(32)).unwrap();
+        assert_eq!(sign_claims(&[absent_key], &MESSAGE_DIGEST, None, &tree_dump, None).map(|_| ()),
+                   Err(format!("address {} is not in the tree", sign(3).1)));
+
+        // A given message would leave the public inputs' nullifier scope unsigned
+        let message_args = ["prove", "sign", "-k", "0x01", "-m", "hello"];
+        assert!(Cli::try_parse_from(message_args).is_ok());
+        assert!(Cli::try_parse_from(message_args.iter().chain(&["-u", "public_inputs.json"])).is_err());
+        assert!(Cli::try_parse_from(["prove", "sign", "-k", "0x01", "-d", "0x00", "-u", "public_inputs.json"]).is_err());
+    }
+
+    #[test]
+    fn prepare_assembles_private_inputs_and_leaves_out_unknown_signers() {
+        let (public_inputs, tree_dump) = two_leaf_tree_dump();
+        let contents = format!("[\"{}\", \"{}\", {{ \"signature\": \"{}\" }}, \"{}\"]", sign(2).0, sign(3).0, sign(1).0, sign(2).0);
+        let signatures = parse_signatures(&contents).unwrap();
+        assert_eq!(parse_signatures(&format!("{}\n\n{}\n", sign(2).0, sign(3).0)).unwrap(), signatures[..2]);
+
+        let (signed_messages, warnings) = prepare_signed_messages(&MESSAGE_DIGEST, signatures, &tree_dump, None).unwrap();
+        assert_eq!(warnings, vec![
+            format!("signature 1: address {} is not in the snapshot", sign(3).1),
+            format!("signature 3: address {} was already signed for", sign(2).1),
+        ]);
+        assert_eq!(signed_messages.iter().map(|signed_message| signed_message.balance).collect::<Vec<_>>(), [U256::from(1500), U256::from(1000)]);
+        let private_inputs = PrivateInputs { signed_messages, ..Default::default() };
+        assert!(validate_private_inputs(&public_inputs, &private_inputs).unwrap().0.is_empty());
+
+        // A signature that recovers no address cannot be placed
+        let bad_signature = vec![(format!("{}1d", &sign(1).0[..130]), None)];
+        assert_eq!(prepare_signed_messages(&MESSAGE_DIGEST, bad_signature, &tree_dump, None).map(|_| ()),
+                   Err(format!("signature 0: {}", ClaimError::InvalidRecoveryId)));
+    }
+
+    #[test]
+    fn message_digest_is_checked_like_the_program_does() {
+        let (mut public_inputs, _) = two_leaf_inputs([U256::from(1), U256::from(2)]);
+        assert_eq!(expected_message_digest(&public_inputs), Ok(MESSAGE_DIGEST));
+
+        // A given digest must be the hash of the message
+        let message = "I own these tokens";
+        public_inputs.message = Some(message.to_string());
+        assert!(expected_message_digest(&public_inputs).unwrap_err().contains("does not match the hash"));
+        public_inputs.message_digest = None;
+        assert_eq!(expected_message_digest(&public_inputs), Ok(eip191_digest(message)));
+
+        // A typed claim must name the tree the proof is generated against
+        public_inputs.message = None;
+        public_inputs.typed_data = Some(TypedData {
+            domain: Eip712Domain {
+                name: "Token Ownership".to_string(),
+                version: "1".to_string(),
+                chain_id: 1,
+                verifying_contract: "0x0000000000000000000000000000000000000000".to_string(),
+            },
+            claim: OwnershipClaim {
+                statement: "I own these tokens".to_string(),
+                merkle_root: format!("0x{}", hex::encode([0x11; 32])),
+                nonce: format!("0x{}", hex::encode([0x22; 32])),
+                expires_at: 0,
+            },
+        });
+        assert!(expected_message_digest(&public_inputs).unwrap_err().contains("does not match the public Merkle root"));
+        public_inputs.typed_data.as_mut().unwrap().claim.merkle_root = format!("0x{}", public_inputs.merkle_root);
+        let typed_digest = eip712_digest(public_inputs.typed_data.as_ref().unwrap());
+        assert_eq!(expected_message_digest(&public_inputs), Ok(typed_digest));
+
+        public_inputs.message = Some(message.to_string());
+        assert!(expected_message_digest(&public_inputs).is_err());
+        public_inputs.message = None;
+        public_inputs.typed_data = None;
+        assert!(expected_message_digest(&public_inputs).is_err());
+    }
+
+    #[test]
+    fn sums_balances_without_overflow() {
+        let (public_inputs, private_inputs) = two_leaf_inputs([U256::MAX - U256::from(1), U256::from(1)]);
+        assert!(validate_private_inputs(&public_inputs, &private_inputs).unwrap().0.is_empty());
+
+        let public_outputs = execute(&public_inputs, &private_inputs).expect("Execution failed");
+        assert!(matches!(public_outputs.balance, BalanceClaim::Exact(total) if total == U256::MAX));
+    }
+
+    #[test]
+    fn overflowing_total_fails_execution() {
+        let (public_inputs, private_inputs) = two_leaf_inputs([U256::MAX, U256::from(1)]);
+        assert_eq!(validate_private_inputs(&public_inputs, &private_inputs).unwrap().0, vec![(1, ClaimError::BalanceOverflow)]);
+
+        assert!(execute(&public_inputs, &private_inputs).is_err());
+    }
+
+    #[test]
+    fn overflowing_total_fails_execution_in_lenient_mode() {
+        let (mut public_inputs, private_inputs) = two_leaf_inputs([U256::MAX, U256::MAX]);
+        public_inputs.claim_mode = ClaimMode::Lenient;
+
+        assert!(execute(&public_inputs, &private_inputs).is_err());
+    }
+
+    #[test]
+    fn proof_of_wrong_depth_fails_execution() {
+        let (mut public_inputs, private_inputs) = two_leaf_inputs([U256::from(1), U256::from(2)]);
+        public_inputs.tree_depth = 2;
+        assert_eq!(validate_private_inputs(&public_inputs, &private_inputs).unwrap().0,
+                   vec![(0, ClaimError::WrongProofDepth), (1, ClaimError::WrongProofDepth)]);
+
+        assert!(execute(&public_inputs, &private_inputs).is_err());
+    }
+
+    #[test]
+    fn high_s_claim_fails_execution() {
+        let (public_inputs, mut private_inputs) = two_leaf_inputs([U256::from(1000), U256::from(1500)]);
+        private_inputs.signed_messages[0].signature = malleable_twin(&sign(1).0).unwrap();
+        assert_eq!(validate_private_inputs(&public_inputs, &private_inputs).unwrap().0, vec![(0, ClaimError::HighS)]);
+        assert!(execute(&public_inputs, &private_inputs).is_err());
+    }
+
+    #[test]
+    fn multiproof_verifies_all_claims_in_one_pass() {
+        let tree_scheme = TreeScheme::Rfc6962;
+        let claims = [sign(1), sign(2), sign(3)];
+        let balances = [U256::from(10), U256::from(20), U256::from(30)];
+        let leaves = claims.iter().zip(balances)
+            .map(|((_, address), balance)| tree_scheme.hash_leaf(address, balance))
+            .collect();
+        let (merkle_root, tree_depth, proofs) = build_tree(tree_scheme, leaves);
+
+        let signed_messages = claims.into_iter().zip(balances).zip(proofs)
+            .map(|(((signature, _), balance), inclusion_branches)| SignedMessage {
+                signature,
+                balance,
+                inclusion_branches: Some(inclusion_branches),
+                ..Default::default()
+            })
+            .collect();
+        let public_inputs = PublicInputs {
+            message_digest: Some(format!("0x{}", hex::encode(MESSAGE_DIGEST))),
+            merkle_root: hex::encode(merkle_root),
+            tree_depth,
+            tree_scheme,
+            ..Default::default()
+        };
+        let mut private_inputs = PrivateInputs { signed_messages, ..Default::default() };
+
+        // Leaves 0 and 1 share a parent and leaf 2 only needs the padding leaf next to it
+        merge_inclusion_proofs(&public_inputs, &mut private_inputs);
+        assert_eq!(private_inputs.multiproof.as_ref().map(|multiproof| multiproof.proof.len()), Some(1));
+        assert_eq!(validate_multiproof(&public_inputs, &private_inputs), Ok(()));
+        assert!(validate_private_inputs(&public_inputs, &private_inputs).unwrap().0.is_empty());
+
+        let public_outputs = execute(&public_inputs, &private_inputs).expect("Execution failed");
+        assert_eq!(public_outputs.claim_count, 3);
+        assert!(matches!(public_outputs.balance, BalanceClaim::Exact(total) if total == U256::from(60)));
+    }
+
+    #[test]
+    fn claims_in_several_roots_commit_root_thresholds_and_aggregate() {
+        let tree_scheme = TreeScheme::Rfc6962;
+        let (signature_a, address_a) = sign(1);
+        let (signature_b, address_b) = sign(2);
+
+        // The top-level tree holds only address A; tree "token_b" holds both addresses
+        let (default_root, default_depth, default_proofs) =
+            build_tree(tree_scheme, vec![tree_scheme.hash_leaf(&address_a, U256::from(10))]);
+        let token_b_leaves = vec![tree_scheme.hash_leaf(&address_a, U256::from(5)), tree_scheme.hash_leaf(&address_b, U256::from(7))];
+        let (token_b_root, token_b_depth, token_b_proofs) = build_tree(tree_scheme, token_b_leaves);
+
+        let claim = |signature: &str, balance: u64, inclusion_branches: &InclusionBranches, root: Option<&str>| SignedMessage {
+            signature: signature.to_string(),
+            balance: U256::from(balance),
+            inclusion_branches: Some(inclusion_branches.clone()),
+            root: root.map(str::to_string),
+            ..Default::default()
+        };
+        let signed_messages = vec![
+            claim(&signature_a, 10, &default_proofs[0], None),
+            claim(&signature_a, 5, &token_b_proofs[0], Some("token_b")),
+            claim(&signature_b, 7, &token_b_proofs[1], Some("token_b")),
+        ];
+        let public_inputs = PublicInputs {
+            message_digest: Some(format!("0x{}", hex::encode(MESSAGE_DIGEST))),
+            merkle_root: hex::encode(default_root),
+            tree_depth: default_depth,
+            tree_scheme,
+            roots: vec![NamedRoot {
+                name: "token_b".to_string(),
+                merkle_root: hex::encode(token_b_root),
+                tree_depth: token_b_depth,
+                tree_scheme,
+                min_balance: Some(U256::from(12)),
+            }],
+            aggregate: Some(WeightedAggregate {
+                weights: [(DEFAULT_ROOT_NAME.to_string(), U256::from(3)), ("token_b".to_string(), U256::from(2))].into(),
+                min_total: None,
+            }),
+            ..Default::default()
+        };
+        let private_inputs = PrivateInputs { signed_messages, ..Default::default() };
+        let (problems, totals) = validate_private_inputs(&public_inputs, &private_inputs).unwrap();
+        assert!(problems.is_empty());
+        assert_eq!(totals, vec![U256::from(10), U256::from(12)]);
+        assert_eq!(validate_aggregate(&public_inputs, &totals), Ok(()));
+
+        let public_outputs = execute(&public_inputs, &private_inputs).expect("Execution failed");
+        // Next to the aggregate, roots only claim their threshold, or zero without one
+        assert!(matches!(public_outputs.balance, BalanceClaim::AtLeast(total) if total == U256::ZERO));
+        assert!(matches!(public_outputs.roots[0].balance, BalanceClaim::AtLeast(total) if total == U256::from(12)));
+        let aggregate = public_outputs.aggregate.as_ref().expect("Aggregate not committed");
+        assert!(matches!(aggregate.balance, BalanceClaim::Exact(total) if total == U256::from(3 * 10 + 2 * 12)));
+        assert_eq!(check_public_outputs(&public_outputs, &public_inputs), Ok(()));
+    }
+
+    #[test]
+    fn storage_proofs_verify_erc20_balances_against_state_root() {
+        // Synthetic `eth_getProof` response for a token whose `balances` mapping at slot 0 holds
+        // balances for the signers of keys 1 and 2; the slot of key 3 is empty
+        let eth_proofs = read_eth_proofs(Path::new(concat!(env!("CARGO_MANIFEST_DIR"), "/fixtures/erc20_get_proof.json")));
+        let balances = [U256::from(1_500_000_000_000_000_000u64), U256::from(250_000_000u64), U256::ZERO];
+        let signed_messages = (1..=3).zip(balances)
+            .map(|(key_byte, balance)| SignedMessage {
+                signature: sign(key_byte).0,
+                balance,
+                ..Default::default()
+            })
+            .collect();
+        let public_inputs = PublicInputs {
+            message_digest: Some(format!("0x{}", hex::encode(MESSAGE_DIGEST))),
+            merkle_root: STATE_ROOT.to_string(),
+            balance_source: BalanceSource::Erc20Storage {
+                token: "0x5fbdb2315678afecb367f032d93f642f64180aa3".to_string(),
+                balances_slot: U256::ZERO,
+            },
+            ..Default::default()
+        };
+        let mut private_inputs = PrivateInputs { signed_messages, ..Default::default() };
+        assert_eq!(attach_eth_proofs(&public_inputs, &mut private_inputs, eth_proofs), Ok(()));
+        assert!(validate_private_inputs(&public_inputs, &private_inputs).unwrap().0.is_empty());
+
+        let public_outputs = execute(&public_inputs, &private_inputs).expect("Execution failed");
+        assert!(matches!(public_outputs.balance, BalanceClaim::Exact(total) if total == balances[0] + balances[1]));
+        assert_eq!(check_public_outputs(&public_outputs, &public_inputs), Ok(()));
+
+        // Claiming more than the stored balance is rejected
+        private_inputs.signed_messages[1].balance += U256::from(1);
+        assert_eq!(validate_private_inputs(&public_inputs, &private_inputs).unwrap().0, vec![(1, ClaimError::StorageBalanceMismatch)]);
+        assert!(execute(&public_inputs, &private_inputs).is_err());
+    }
+
+    // Signatures by the given keys of the Safe's message for the test digest on the test chain,
+    // sorted by owner address as the Safe requires
+    fn safe_signatures(safe: &str, key_bytes: &[u8]) -> Vec<String> {
+        let safe_digest = safe_message_digest(&hex_to_address(safe), CHAIN_ID, &MESSAGE_DIGEST);
+        let mut signing_keys: Vec<SigningKey> = key_bytes.iter()
+            .map(|key_byte| SigningKey::from_slice(&[*key_byte; 32]).unwrap())
+            .collect();
+        signing_keys.sort_by_key(signing_key_address);
+        signing_keys.iter().map(|signing_key| sign_digest(signing_key, &safe_digest)).collect()
+    }
+
+    // Native ETH claims by the signers of keys 1 to 3 with proofs from the fixture batch of
+    // synthetic `eth_getProof` responses; the account of key 3 does not exist and holds no ETH
+    fn native_eth_inputs() -> (PublicInputs, PrivateInputs) {
+        let eth_proofs = read_eth_proofs(Path::new(concat!(env!("CARGO_MANIFEST_DIR"), "/fixtures/native_get_proof.json")));
+        let balances = [U256::from(3_200_000_000_000_000_000u64), U256::from(42_000_000_000_000u64), U256::ZERO];
+        let signed_messages = (1..=3).zip(balances)
+            .map(|(key_byte, balance)| SignedMessage {
+                signature: sign(key_byte).0,
+                balance,
+                ..Default::default()
+            })
+            .collect();
+        let public_inputs = PublicInputs {
+            message_digest: Some(format!("0x{}", hex::encode(MESSAGE_DIGEST))),
+            merkle_root: STATE_ROOT.to_string(),
+            balance_source: BalanceSource::Native,
+            chain_id: Some(CHAIN_ID),
+            ..Default::default()
+        };
+        let mut private_inputs = PrivateInputs { signed_messages, ..Default::default() };
+        attach_eth_proofs(&public_inputs, &mut private_inputs, eth_proofs).expect("Fixture does not cover the claims");
+        (public_inputs, private_inputs)
+    }
+
+    #[test]
+    fn account_proofs_verify_native_eth_balances_against_state_root() {
+        let (public_inputs, mut private_inputs) = native_eth_inputs();
+        assert!(validate_private_inputs(&public_inputs, &private_inputs).unwrap().0.is_empty());
+
+        let public_outputs = execute(&public_inputs, &private_inputs).expect("Execution failed");
+        assert!(matches!(public_outputs.balance, BalanceClaim::Exact(total) if total == U256::from(3_200_042_000_000_000_000u64)));
+        assert_eq!(check_public_outputs(&public_outputs, &public_inputs), Ok(()));
+
+        // Claiming ETH for an account that does not exist is rejected
+        private_inputs.signed_messages[2].balance = U256::from(1);
+        assert_eq!(validate_private_inputs(&public_inputs, &private_inputs).unwrap().0, vec![(2, ClaimError::AccountBalanceMismatch)]);
+        assert!(execute(&public_inputs, &private_inputs).is_err());
+    }
+
+    #[test]
+    fn safe_claim_counts_balance_once_when_owners_meet_threshold() {
+        // The fixture Safe holds 12.5 ETH and has owners 1, 2 and 4 with a threshold of 2
+        let safe = "0x5afe5afe5afe5afe5afe5afe5afe5afe5afe5afe";
+        let safe_fixture = concat!(env!("CARGO_MANIFEST_DIR"), "/fixtures/safe_get_proof.json");
+        let (mut public_inputs, mut private_inputs) = native_eth_inputs();
+        private_inputs.safe_claims.push(SafeClaim {
+            safe: safe.to_string(),
+            balance: U256::from(12_500_000_000_000_000_000u64),
+            owner_signatures: safe_signatures(safe, &[1, 2]),
+            ..Default::default()
+        });
+        attach_eth_proofs(&public_inputs, &mut private_inputs, read_eth_proofs(Path::new(safe_fixture))).unwrap();
+        assert!(validate_private_inputs(&public_inputs, &private_inputs).unwrap().0.is_empty());
+        assert_eq!(validate_safe_claims(&public_inputs, &private_inputs), Ok(()));
+
+        let public_outputs = execute(&public_inputs, &private_inputs).expect("Execution failed");
+        assert!(matches!(public_outputs.balance, BalanceClaim::Exact(total) if total == U256::from(15_700_042_000_000_000_000u64)));
+        assert_eq!(check_public_outputs(&public_outputs, &public_inputs), Ok(()));
+
+        // The same Safe without the 0x prefix and in upper case is still a duplicate
+        private_inputs.safe_claims.push(SafeClaim {
+            safe: "5AFE5AFE5AFE5AFE5AFE5AFE5AFE5AFE5AFE5AFE".to_string(),
+            balance: U256::from(12_500_000_000_000_000_000u64),
+            owner_signatures: safe_signatures(safe, &[1, 2]),
+            ..Default::default()
+        });
+        attach_eth_proofs(&public_inputs, &mut private_inputs, read_eth_proofs(Path::new(safe_fixture))).unwrap();
+        assert_eq!(validate_private_inputs(&public_inputs, &private_inputs).unwrap().0, vec![(4, ClaimError::DuplicateAddress)]);
+        assert!(execute(&public_inputs, &private_inputs).is_err());
+
+        // A malformed Safe address is rejected like any other invalid claim
+        private_inputs.safe_claims[1].safe = "0x5afe".to_string();
+        assert_eq!(validate_private_inputs(&public_inputs, &private_inputs).unwrap().0, vec![(4, ClaimError::InvalidAddress)]);
+        private_inputs.safe_claims.pop();
+
+        // Signatures must be sorted by owner, as the Safe checks them
+        private_inputs.safe_claims[0].owner_signatures.reverse();
+        attach_eth_proofs(&public_inputs, &mut private_inputs, read_eth_proofs(Path::new(safe_fixture))).unwrap();
+        assert_eq!(validate_private_inputs(&public_inputs, &private_inputs).unwrap().0, vec![(3, ClaimError::UnsortedSafeOwners)]);
+
+        // One owner's signature is below the threshold
+        private_inputs.safe_claims[0].owner_signatures = safe_signatures(safe, &[1]);
+        attach_eth_proofs(&public_inputs, &mut private_inputs, read_eth_proofs(Path::new(safe_fixture))).unwrap();
+        assert_eq!(validate_private_inputs(&public_inputs, &private_inputs).unwrap().0, vec![(3, ClaimError::SafeThresholdNotMet)]);
+
+        // Address 3 signed but is not an owner
+        private_inputs.safe_claims[0].owner_signatures = safe_signatures(safe, &[1, 3]);
+        attach_eth_proofs(&public_inputs, &mut private_inputs, read_eth_proofs(Path::new(safe_fixture))).unwrap();
+        assert_eq!(validate_private_inputs(&public_inputs, &private_inputs).unwrap().0, vec![(3, ClaimError::NotSafeOwner)]);
+        assert!(execute(&public_inputs, &private_inputs).is_err());
+
+        // Owners sign for one chain, so the claim needs its chain id
+        public_inputs.chain_id = None;
+        assert!(validate_safe_claims(&public_inputs, &private_inputs).is_err());
+    }
+
+    #[test]
+    fn block_header_binds_state_root_to_block_hash() {
+        // Synthetic header of block 19000000 whose state root is that of the fixtures
+        let block_hash = "0x33de116d812ec041bd1df84799c22f019e26a217469c987fae9de35b201a019f";
+        let (mut public_inputs, mut private_inputs) = native_eth_inputs();
+        public_inputs.block_hash = Some(block_hash.to_string());
+        assert_eq!(validate_block_header(&public_inputs, &private_inputs), Err("a public block hash requires the block header".to_string()));
+
+        private_inputs.block_header = Some(read_block_header(Path::new(concat!(env!("CARGO_MANIFEST_DIR"), "/fixtures/block_header.json"))));
+        assert_eq!(validate_block_header(&public_inputs, &private_inputs), Ok(()));
+
+        let public_outputs = execute(&public_inputs, &private_inputs).expect("Execution failed");
+        let block = public_outputs.block.as_ref().expect("Block not committed");
+        assert_eq!((block.number, block.hash), (19_000_000, hex_to_bytes32(block_hash)));
+        assert_eq!(check_public_outputs(&public_outputs, &public_inputs), Ok(()));
+
+        // The header must carry the public state root
+        public_inputs.merkle_root = hex::encode([0x11; 32]);
+        assert!(validate_block_header(&public_inputs, &private_inputs).is_err());
+        assert!(execute(&public_inputs, &private_inputs).is_err());
+    }
+}